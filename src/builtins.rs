@@ -0,0 +1,102 @@
+use std::io::{self, BufRead, Write};
+
+use crate::interpreter::heap::Heap;
+use crate::interpreter::value::Value;
+use crate::parser::ast::Type;
+
+/// the type of a parameter, which the checker matches the argument against
+pub enum Param {
+    /// a String or an array of any type
+    Sequence,
+}
+
+/// the parameters a builtin accepts
+pub enum Params {
+    Fixed(&'static [Param]),
+    /// any number of arguments of any type except void
+    Variadic,
+}
+
+/// a function built into the interpreter, called like `out!(...)`
+pub struct Builtin {
+    pub name: &'static str,
+    pub params: Params,
+    pub return_type: Type,
+    pub function: fn(&mut dyn Write, &Heap, Vec<Value>) -> Result<Value, String>,
+}
+
+static BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "out",
+        params: Params::Variadic,
+        return_type: Type::Void,
+        function: out,
+    },
+    Builtin {
+        name: "in",
+        params: Params::Fixed(&[]),
+        return_type: Type::String,
+        function: input,
+    },
+    Builtin {
+        name: "len",
+        params: Params::Fixed(&[Param::Sequence]),
+        return_type: Type::Int,
+        function: len,
+    },
+];
+
+pub fn lookup(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+/// prints all arguments without separators, followed by a newline
+fn out(output: &mut dyn Write, heap: &Heap, arguments: Vec<Value>) -> Result<Value, String> {
+    let line: String = arguments
+        .iter()
+        .map(|value| value.display(heap).to_string())
+        .collect();
+
+    writeln!(output, "{}", line).map_err(|e| format!("failed to write output: {}", e))?;
+
+    Ok(Value::Null)
+}
+
+/// reads a line from stdin without the line break, or null at the end of input
+fn input(output: &mut dyn Write, _: &Heap, _: Vec<Value>) -> Result<Value, String> {
+    // a prompt printed with `out!` has to be visible before waiting for input
+    output
+        .flush()
+        .map_err(|e| format!("failed to write output: {}", e))?;
+
+    let mut line = String::new();
+    let read = io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| format!("failed to read input: {}", e))?;
+
+    if read == 0 {
+        return Ok(Value::Null);
+    }
+
+    let line = line.strip_suffix('\n').unwrap_or(&line);
+    let line = line.strip_suffix('\r').unwrap_or(line);
+
+    Ok(Value::String(line.to_string()))
+}
+
+/// the number of elements of an array or characters of a String
+fn len(_: &mut dyn Write, heap: &Heap, arguments: Vec<Value>) -> Result<Value, String> {
+    let len = match &arguments[0] {
+        Value::Array(handle) => heap.array(*handle).elements.len(),
+        Value::String(s) => s.chars().count(),
+        value => {
+            return Err(format!(
+                "cannot get the length of {}",
+                value.type_name(heap)
+            ))
+        }
+    };
+
+    Ok(Value::Int(len as i64))
+}
@@ -0,0 +1,1222 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::builtins::{self, Param, Params};
+use crate::lexer::token::{Token, TokenSpan, TokenType};
+use crate::parser::ast::{
+    ClassDecl, Decl, Entry, Expr, FunctionDecl, Program, Stmt, Type, VarDecl,
+};
+
+#[derive(Debug, Clone)]
+pub struct TypeError {
+    pub code: &'static str,
+    pub message: String,
+    pub span: TokenSpan,
+}
+
+/// static type checker which runs between the parser and the interpreter
+#[derive(Debug, Default, Clone)]
+pub struct Checker {
+    functions: HashMap<String, Rc<FunctionDecl>>,
+    classes: HashMap<String, Rc<ClassDecl>>,
+    scopes: Vec<HashMap<String, Type>>,
+    return_type: Option<Type>,
+    current_class: Option<Rc<ClassDecl>>,
+    errors: Vec<TypeError>,
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        Checker::default()
+    }
+
+    pub fn check(mut self, program: &Program) -> Result<(), Vec<TypeError>> {
+        for decl in &program.declarations {
+            self.declare(decl);
+        }
+
+        // globals live in the outermost scope
+        self.scopes.push(HashMap::new());
+
+        for decl in &program.declarations {
+            self.check_decl(decl);
+        }
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    /// checks an input of the REPL, everything declared by earlier inputs stays visible
+    pub fn check_entries(&mut self, entries: &[Entry]) -> Result<(), Vec<TypeError>> {
+        for entry in entries {
+            if let Entry::Decl(decl) = entry {
+                self.declare(decl);
+            }
+        }
+
+        if self.scopes.is_empty() {
+            self.scopes.push(HashMap::new());
+        }
+
+        for entry in entries {
+            match entry {
+                Entry::Decl(decl) => self.check_decl(decl),
+                // statements run in their own scope, only declarations are kept
+                Entry::Stmt(stmt) => {
+                    self.scopes.push(HashMap::new());
+                    self.check_stmt(stmt);
+                    self.scopes.pop();
+                }
+                Entry::Expr(expr) => {
+                    self.check_expr(expr);
+                }
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    fn declare(&mut self, decl: &Decl) {
+        match decl {
+            Decl::Function(function) => {
+                let name = &function.name;
+                if self
+                    .functions
+                    .insert(name.lexeme.clone(), function.clone())
+                    .is_some()
+                {
+                    self.error(
+                        "E0201",
+                        format!("function '{}' is already defined", name.lexeme),
+                        name.span,
+                    );
+                }
+            }
+            Decl::Class(class) => {
+                let name = &class.name;
+                if self
+                    .classes
+                    .insert(name.lexeme.clone(), class.clone())
+                    .is_some()
+                {
+                    self.error(
+                        "E0201",
+                        format!("class '{}' is already defined", name.lexeme),
+                        name.span,
+                    );
+                }
+            }
+            Decl::Var(_) => {}
+        }
+    }
+
+    fn check_decl(&mut self, decl: &Decl) {
+        match decl {
+            Decl::Function(function) => self.check_function(function),
+            Decl::Class(class) => self.check_class(class),
+            Decl::Var(var) => self.check_var(var),
+        }
+    }
+
+    fn check_class(&mut self, class: &Rc<ClassDecl>) {
+        self.current_class = Some(class.clone());
+
+        let mut field_names = HashMap::new();
+        for field in &class.fields {
+            if field_names.insert(&field.name.lexeme, ()).is_some() {
+                self.error(
+                    "E0201",
+                    format!("field '{}' is already defined", field.name.lexeme),
+                    field.name.span,
+                );
+            }
+
+            self.check_declared_type(&field.ty, &field.name);
+
+            // field initializers can use `this`, but don't see any locals
+            self.scopes.push(HashMap::new());
+            self.check_initializer(field);
+            self.scopes.pop();
+        }
+
+        if let Some(constructor) = &class.constructor {
+            self.check_function(constructor);
+        }
+
+        for (i, method) in class.methods.iter().enumerate() {
+            if class.methods[..i]
+                .iter()
+                .any(|m| m.name.lexeme == method.name.lexeme)
+            {
+                self.error(
+                    "E0201",
+                    format!("method '{}' is already defined", method.name.lexeme),
+                    method.name.span,
+                );
+            }
+
+            self.check_function(method);
+        }
+
+        self.current_class = None;
+    }
+
+    fn check_function(&mut self, function: &FunctionDecl) {
+        self.scopes.push(HashMap::new());
+
+        for param in &function.params {
+            self.check_declared_type(&param.ty, &param.name);
+            self.define(&param.name, param.ty.clone());
+        }
+
+        if function.return_type != Type::Void {
+            self.check_declared_type(&function.return_type, &function.name);
+        }
+
+        let enclosing = self.return_type.replace(function.return_type.clone());
+        for stmt in &function.body {
+            self.check_stmt(stmt);
+        }
+        self.return_type = enclosing;
+
+        if function.return_type != Type::Void && !Checker::always_returns(&function.body) {
+            self.error(
+                "E0209",
+                format!(
+                    "function '{}' does not return a value on every path",
+                    function.name.lexeme
+                ),
+                function.name.span,
+            );
+        }
+
+        self.scopes.pop();
+    }
+
+    /// true if executing the statements always ends in a return statement
+    pub(crate) fn always_returns(statements: &[Stmt]) -> bool {
+        statements.iter().any(|stmt| match stmt {
+            Stmt::Return { .. } => true,
+            Stmt::Block(statements) => Checker::always_returns(statements),
+            Stmt::If {
+                then_branch,
+                else_branch: Some(else_branch),
+                ..
+            } => {
+                Checker::always_returns(std::slice::from_ref(then_branch))
+                    && Checker::always_returns(std::slice::from_ref(else_branch))
+            }
+            // there is no `break`, so an endless loop is only left by returning
+            Stmt::While { condition, .. } => Checker::is_true(condition),
+            Stmt::For { condition, .. } => condition.as_ref().is_none_or(Checker::is_true),
+            _ => false,
+        })
+    }
+
+    /// the literal `true`, which makes a loop endless
+    fn is_true(expr: &Expr) -> bool {
+        match expr {
+            Expr::LiteralTrue(_) => true,
+            Expr::Grouping(expr) => Checker::is_true(expr),
+            _ => false,
+        }
+    }
+
+    fn check_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression(expr) => {
+                self.check_expr(expr);
+            }
+            Stmt::Var(var) => self.check_var(var),
+            Stmt::Block(statements) => {
+                self.scopes.push(HashMap::new());
+                for stmt in statements {
+                    self.check_stmt(stmt);
+                }
+                self.scopes.pop();
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.check_condition(condition);
+                self.check_stmt(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.check_stmt(else_branch);
+                }
+            }
+            Stmt::While { condition, body } => {
+                self.check_condition(condition);
+                self.check_stmt(body);
+            }
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+            } => {
+                self.scopes.push(HashMap::new());
+                if let Some(initializer) = initializer {
+                    self.check_stmt(initializer);
+                }
+                if let Some(condition) = condition {
+                    self.check_condition(condition);
+                }
+                if let Some(increment) = increment {
+                    self.check_expr(increment);
+                }
+                self.check_stmt(body);
+                self.scopes.pop();
+            }
+            Stmt::ForIn {
+                name,
+                iterable,
+                body,
+            } => {
+                let element = match self.check_expr(iterable) {
+                    Some(Type::Array(element)) => Some(*element),
+                    Some(ty) => {
+                        self.error(
+                            "E0206",
+                            format!("expected an array but found {}", ty),
+                            iterable.span(),
+                        );
+                        None
+                    }
+                    None => None,
+                };
+
+                self.scopes.push(HashMap::new());
+                // the loop variable is still defined after an error to avoid follow-up errors
+                self.define(name, element.unwrap_or(Type::Null));
+                self.check_stmt(body);
+                self.scopes.pop();
+            }
+            Stmt::Return { keyword, value } => self.check_return(keyword, value),
+        }
+    }
+
+    fn check_return(&mut self, keyword: &Token, value: &Option<Expr>) {
+        let expected = match &self.return_type {
+            Some(ty) => ty.clone(),
+            None => {
+                self.error(
+                    "E0208",
+                    "'return' outside of a function".to_string(),
+                    keyword.span,
+                );
+                return;
+            }
+        };
+
+        match value {
+            Some(expr) => {
+                let found = self.check_expr(expr);
+
+                if expected == Type::Void {
+                    self.error(
+                        "E0208",
+                        "cannot return a value from a function without return type".to_string(),
+                        expr.span(),
+                    );
+                } else if let Some(found) = found {
+                    self.expect_assignable(&expected, &found, expr.span());
+                }
+            }
+            None => {
+                if expected != Type::Void {
+                    self.error(
+                        "E0208",
+                        format!("expected a return value of type {}", expected),
+                        keyword.span,
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_var(&mut self, var: &VarDecl) {
+        self.check_declared_type(&var.ty, &var.name);
+        self.check_initializer(var);
+
+        let redefined = self
+            .scopes
+            .last()
+            .is_some_and(|scope| scope.contains_key(&var.name.lexeme));
+        if redefined {
+            self.error(
+                "E0201",
+                format!(
+                    "variable '{}' is already defined in this scope",
+                    var.name.lexeme
+                ),
+                var.name.span,
+            );
+        }
+
+        self.define(&var.name, var.ty.clone());
+    }
+
+    fn check_initializer(&mut self, var: &VarDecl) {
+        if let Some(initializer) = &var.initializer {
+            if let Some(found) = self.check_expr(initializer) {
+                self.expect_assignable(&var.ty, &found, initializer.span());
+            }
+        }
+    }
+
+    fn check_declared_type(&mut self, ty: &Type, name: &Token) {
+        match ty {
+            Type::Void | Type::Null => {
+                self.error(
+                    "E0210",
+                    format!("'{}' can't have type {}", name.lexeme, ty),
+                    name.span,
+                );
+            }
+            Type::Array(element) => self.check_declared_type(element, name),
+            Type::Class(class) if !self.classes.contains_key(class) => {
+                self.error(
+                    "E0203",
+                    format!("unknown type '{}' for '{}'", class, name.lexeme),
+                    name.span,
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn check_condition(&mut self, condition: &Expr) {
+        if let Some(ty) = self.check_expr(condition) {
+            if ty != Type::Bool {
+                self.error(
+                    "E0206",
+                    format!("expected bool condition but found {}", ty),
+                    condition.span(),
+                );
+            }
+        }
+    }
+
+    /// returns the type of the expression or None if it contains an error
+    fn check_expr(&mut self, expr: &Expr) -> Option<Type> {
+        match expr {
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.check_expr(left);
+                let right = self.check_expr(right);
+
+                self.check_binary(&left?, operator, &right?)
+            }
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.check_expr(left);
+                let right = self.check_expr(right);
+                let (left, right) = (left?, right?);
+
+                if left == Type::Bool && right == Type::Bool {
+                    Some(Type::Bool)
+                } else {
+                    self.error(
+                        "E0207",
+                        format!(
+                            "cannot apply '{}' to {} and {}",
+                            operator.lexeme, left, right
+                        ),
+                        operator.span,
+                    );
+                    None
+                }
+            }
+            Expr::Unary { operator, right } => {
+                let right = self.check_expr(right)?;
+
+                match (operator.token_type, &right) {
+                    (TokenType::Minus, Type::Int | Type::Double) => Some(right),
+                    (TokenType::Bang, Type::Bool) => Some(right),
+                    _ => {
+                        self.error(
+                            "E0207",
+                            format!("cannot apply unary '{}' to {}", operator.lexeme, right),
+                            operator.span,
+                        );
+                        None
+                    }
+                }
+            }
+            Expr::Grouping(expr) => self.check_expr(expr),
+            Expr::Variable(name) => self.lookup(name),
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+            } => self.check_call(callee, paren, arguments),
+            Expr::Get { object, name } => {
+                let class = self.check_object(object)?;
+
+                match class.find_field(&name.lexeme) {
+                    Some(field) => Some(field.ty.clone()),
+                    None => {
+                        self.error(
+                            "E0204",
+                            format!("'{}' has no field '{}'", class.name.lexeme, name.lexeme),
+                            name.span,
+                        );
+                        None
+                    }
+                }
+            }
+            Expr::Assign {
+                name,
+                operator,
+                value,
+            } => {
+                let target = self.lookup(name);
+                let found = self.check_expr(value);
+
+                self.check_assignment(&target?, operator, &found?, value.span())
+            }
+            Expr::Set {
+                object,
+                name,
+                operator,
+                value,
+            } => {
+                let class = self.check_object(object);
+                let found = self.check_expr(value);
+
+                let field = match class?.find_field(&name.lexeme) {
+                    Some(field) => field.ty.clone(),
+                    None => {
+                        self.error(
+                            "E0204",
+                            format!("unknown field '{}'", name.lexeme),
+                            name.span,
+                        );
+                        return None;
+                    }
+                };
+
+                self.check_assignment(&field, operator, &found?, value.span())
+            }
+            Expr::Index { object, index, .. } => self.check_index(object, index),
+            Expr::SetIndex {
+                object,
+                index,
+                operator,
+                value,
+            } => {
+                let element = self.check_index(object, index);
+                let found = self.check_expr(value);
+
+                self.check_assignment(&element?, operator, &found?, value.span())
+            }
+            Expr::Array(elements, _) => {
+                let mut element_type = None;
+                let mut valid = true;
+
+                for element in elements {
+                    let found = match self.check_expr(element) {
+                        Some(found) => found,
+                        None => {
+                            valid = false;
+                            continue;
+                        }
+                    };
+
+                    element_type = match element_type {
+                        None => Some(found),
+                        Some(ty) => match ty.common(&found) {
+                            Some(common) => Some(common),
+                            None => {
+                                self.error(
+                                    "E0206",
+                                    format!("expected {} but found {}", ty, found),
+                                    element.span(),
+                                );
+                                valid = false;
+                                Some(ty)
+                            }
+                        },
+                    };
+                }
+
+                if element_type == Some(Type::Void) {
+                    self.error(
+                        "E0206",
+                        "expected a value but found void".to_string(),
+                        expr.span(),
+                    );
+                    return None;
+                }
+
+                // the elements of an empty literal are void, so it fits every array type
+                let element_type = element_type.unwrap_or(Type::Void);
+                valid.then(|| Type::Array(Box::new(element_type)))
+            }
+            Expr::Builtin {
+                name,
+                paren,
+                arguments,
+            } => self.check_builtin(name, paren, arguments),
+            Expr::This(keyword) => match &self.current_class {
+                Some(class) => Some(Type::Class(class.name.lexeme.clone())),
+                None => {
+                    self.error(
+                        "E0211",
+                        "'this' used outside of a class".to_string(),
+                        keyword.span,
+                    );
+                    None
+                }
+            },
+            Expr::New {
+                keyword,
+                class,
+                paren,
+                arguments,
+            } => {
+                let span = keyword.span.to(paren.span);
+
+                let decl = match self.classes.get(&class.lexeme) {
+                    Some(decl) => decl.clone(),
+                    None => {
+                        self.error(
+                            "E0203",
+                            format!("unknown class '{}'", class.lexeme),
+                            class.span,
+                        );
+                        self.check_arguments_only(arguments);
+                        return None;
+                    }
+                };
+
+                match &decl.constructor {
+                    Some(constructor) => self.check_arguments(constructor, arguments, span),
+                    None if !arguments.is_empty() => {
+                        self.check_arguments_only(arguments);
+                        self.error(
+                            "E0212",
+                            format!(
+                                "'{}' has no constructor taking {} arguments",
+                                class.lexeme,
+                                arguments.len()
+                            ),
+                            span,
+                        );
+                    }
+                    None => {}
+                }
+
+                Some(Type::Class(class.lexeme.clone()))
+            }
+            Expr::LiteralTrue(_) | Expr::LiteralFalse(_) => Some(Type::Bool),
+            Expr::LiteralNull(_) => Some(Type::Null),
+            Expr::LiteralInt(..) => Some(Type::Int),
+            Expr::LiteralDouble(..) => Some(Type::Double),
+            Expr::LiteralString(..) => Some(Type::String),
+            Expr::LiteralChar(..) => Some(Type::Char),
+        }
+    }
+
+    fn check_binary(&mut self, left: &Type, operator: &Token, right: &Type) -> Option<Type> {
+        let numeric = |ty: &Type| matches!(ty, Type::Int | Type::Double);
+
+        let result = match operator.token_type {
+            TokenType::Plus if *left == Type::String || *right == Type::String => {
+                if *left == Type::Void || *right == Type::Void {
+                    None
+                } else {
+                    Some(Type::String)
+                }
+            }
+            TokenType::Plus
+            | TokenType::Minus
+            | TokenType::Star
+            | TokenType::Slash
+            | TokenType::Remainder
+            | TokenType::Power => {
+                if *left == Type::Int && *right == Type::Int {
+                    Some(Type::Int)
+                } else if numeric(left) && numeric(right) {
+                    Some(Type::Double)
+                } else {
+                    None
+                }
+            }
+            TokenType::Greater
+            | TokenType::Greaterequal
+            | TokenType::Less
+            | TokenType::Lessequal => {
+                if (numeric(left) && numeric(right))
+                    || (*left == Type::String && *right == Type::String)
+                    || (*left == Type::Char && *right == Type::Char)
+                {
+                    Some(Type::Bool)
+                } else {
+                    self.error(
+                        "E0207",
+                        format!("cannot compare {} with {}", left, right),
+                        operator.span,
+                    );
+                    return None;
+                }
+            }
+            TokenType::Equalequal | TokenType::Bangequal => {
+                if Checker::is_assignable(left, right) || Checker::is_assignable(right, left) {
+                    Some(Type::Bool)
+                } else {
+                    self.error(
+                        "E0207",
+                        format!("cannot compare {} with {}", left, right),
+                        operator.span,
+                    );
+                    return None;
+                }
+            }
+            _ => None,
+        };
+
+        if result.is_none() {
+            self.error(
+                "E0207",
+                format!(
+                    "cannot apply '{}' to {} and {}",
+                    operator.lexeme, left, right
+                ),
+                operator.span,
+            );
+        }
+
+        result
+    }
+
+    fn check_assignment(
+        &mut self,
+        target: &Type,
+        operator: &Token,
+        value: &Type,
+        span: TokenSpan,
+    ) -> Option<Type> {
+        let result = match operator.token_type.compound_operator() {
+            Some(token_type) => {
+                let operator = Token {
+                    token_type,
+                    lexeme: operator.lexeme.clone(),
+                    span: operator.span,
+                };
+
+                self.check_binary(target, &operator, value)?
+            }
+            None => value.clone(),
+        };
+
+        self.expect_assignable(target, &result, span);
+
+        Some(target.clone())
+    }
+
+    fn check_call(&mut self, callee: &Expr, paren: &Token, arguments: &[Expr]) -> Option<Type> {
+        let span = callee.span().to(paren.span);
+
+        let function = match callee {
+            Expr::Variable(name) => match self.functions.get(&name.lexeme) {
+                Some(function) => function.clone(),
+                None => {
+                    self.error(
+                        "E0202",
+                        format!("undefined function '{}'", name.lexeme),
+                        name.span,
+                    );
+                    self.check_arguments_only(arguments);
+                    return None;
+                }
+            },
+            Expr::Get { object, name } => {
+                let class = match self.check_object(object) {
+                    Some(class) => class,
+                    None => {
+                        self.check_arguments_only(arguments);
+                        return None;
+                    }
+                };
+
+                match class.find_method(&name.lexeme) {
+                    Some(method) => method.clone(),
+                    None => {
+                        self.error(
+                            "E0204",
+                            format!("'{}' has no method '{}'", class.name.lexeme, name.lexeme),
+                            name.span,
+                        );
+                        self.check_arguments_only(arguments);
+                        return None;
+                    }
+                }
+            }
+            _ => {
+                self.error(
+                    "E0213",
+                    "expression is not callable".to_string(),
+                    callee.span(),
+                );
+                self.check_arguments_only(arguments);
+                return None;
+            }
+        };
+
+        self.check_arguments(&function, arguments, span);
+
+        Some(function.return_type.clone())
+    }
+
+    fn check_arguments(&mut self, function: &FunctionDecl, arguments: &[Expr], span: TokenSpan) {
+        if function.params.len() != arguments.len() {
+            self.error(
+                "E0212",
+                format!(
+                    "'{}' expects {} arguments but got {}",
+                    function.name.lexeme,
+                    function.params.len(),
+                    arguments.len()
+                ),
+                span,
+            );
+            self.check_arguments_only(arguments);
+            return;
+        }
+
+        for (param, argument) in function.params.iter().zip(arguments) {
+            if let Some(found) = self.check_expr(argument) {
+                self.expect_assignable(&param.ty, &found, argument.span());
+            }
+        }
+    }
+
+    fn check_builtin(&mut self, name: &Token, paren: &Token, arguments: &[Expr]) -> Option<Type> {
+        let builtin = match builtins::lookup(&name.lexeme) {
+            Some(builtin) => builtin,
+            None => {
+                self.error(
+                    "E0205",
+                    format!("unknown builtin '{}!'", name.lexeme),
+                    name.span,
+                );
+                self.check_arguments_only(arguments);
+                return None;
+            }
+        };
+
+        match builtin.params {
+            Params::Fixed(params) if params.len() != arguments.len() => {
+                self.error(
+                    "E0212",
+                    format!(
+                        "'{}!' expects {} arguments but got {}",
+                        builtin.name,
+                        params.len(),
+                        arguments.len()
+                    ),
+                    name.span.to(paren.span),
+                );
+                self.check_arguments_only(arguments);
+            }
+            Params::Fixed(params) => {
+                for (param, argument) in params.iter().zip(arguments) {
+                    let found = match self.check_expr(argument) {
+                        Some(found) => found,
+                        None => continue,
+                    };
+
+                    match param {
+                        Param::Sequence => {
+                            if !matches!(found, Type::String | Type::Array(_)) {
+                                self.error(
+                                    "E0206",
+                                    format!("expected String or an array but found {}", found),
+                                    argument.span(),
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            Params::Variadic => {
+                for argument in arguments {
+                    if let Some(Type::Void) = self.check_expr(argument) {
+                        self.error(
+                            "E0206",
+                            "expected a value but found void".to_string(),
+                            argument.span(),
+                        );
+                    }
+                }
+            }
+        }
+
+        Some(builtin.return_type.clone())
+    }
+
+    /// checks the arguments of a call which can't be resolved
+    fn check_arguments_only(&mut self, arguments: &[Expr]) {
+        for argument in arguments {
+            self.check_expr(argument);
+        }
+    }
+
+    /// returns the element type of an indexed array
+    fn check_index(&mut self, object: &Expr, index: &Expr) -> Option<Type> {
+        let array = self.check_expr(object);
+
+        if let Some(ty) = self.check_expr(index) {
+            if ty != Type::Int {
+                self.error(
+                    "E0206",
+                    format!("expected int index but found {}", ty),
+                    index.span(),
+                );
+            }
+        }
+
+        match array? {
+            Type::Array(element) => Some(*element),
+            ty => {
+                self.error(
+                    "E0206",
+                    format!("expected an array but found {}", ty),
+                    object.span(),
+                );
+                None
+            }
+        }
+    }
+
+    fn check_object(&mut self, object: &Expr) -> Option<Rc<ClassDecl>> {
+        match self.check_expr(object)? {
+            Type::Class(name) => self.classes.get(&name).cloned(),
+            ty => {
+                self.error(
+                    "E0206",
+                    format!("expected an object but found {}", ty),
+                    object.span(),
+                );
+                None
+            }
+        }
+    }
+
+    fn expect_assignable(&mut self, target: &Type, source: &Type, span: TokenSpan) {
+        if !Checker::is_assignable(target, source) {
+            self.error(
+                "E0206",
+                format!("expected {} but found {}", target, source),
+                span,
+            );
+        }
+    }
+
+    fn is_assignable(target: &Type, source: &Type) -> bool {
+        match (target, source) {
+            (Type::Double, Type::Int) => true,
+            (Type::String | Type::Class(_) | Type::Array(_) | Type::Null, Type::Null) => true,
+            (Type::Array(element), Type::Array(source)) => element.fits_elements(source),
+            _ => target == source && *target != Type::Void,
+        }
+    }
+
+    fn define(&mut self, name: &Token, ty: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), ty);
+        }
+    }
+
+    fn lookup(&mut self, name: &Token) -> Option<Type> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(ty) = scope.get(&name.lexeme) {
+                return Some(ty.clone());
+            }
+        }
+
+        self.error(
+            "E0202",
+            format!("undefined variable '{}'", name.lexeme),
+            name.span,
+        );
+        None
+    }
+
+    fn error(&mut self, code: &'static str, message: String, span: TokenSpan) {
+        self.errors.push(TypeError {
+            code,
+            message,
+            span,
+        });
+    }
+}
+
+#[cfg(test)]
+fn check_source(source: &str) -> Result<(), Vec<TypeError>> {
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    let tokens = Lexer::from_string(source.to_string()).lex_tokens().0;
+    let program = Parser::new(tokens).parse().unwrap();
+
+    Checker::new().check(&program)
+}
+
+#[test]
+fn test_valid_program() {
+    let source = "
+        class Test {
+            int num = 2;
+
+            fn compute(int n) > double {
+                return n * this.num + 0.5;
+            }
+        }
+
+        fn main() > int {
+            Test t = new Test();
+            double d = t.compute(13);
+
+            if (d > 10) {
+                return 0;
+            } else {
+                return 1;
+            }
+        }
+    ";
+
+    assert!(check_source(source).is_ok());
+}
+
+#[test]
+fn test_type_mismatches() {
+    let source = "
+        fn sum(int a, int b) > int {
+            return a + b;
+        }
+
+        fn main() {
+            int a = \"a\";
+            bool b = \"a\" < 3;
+            sum(1, true);
+        }
+    ";
+
+    let errors = check_source(source).unwrap_err();
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+
+    assert_eq!(
+        messages,
+        vec![
+            "expected int but found String",
+            "cannot compare String with int",
+            "expected int but found bool",
+        ]
+    );
+}
+
+#[test]
+fn test_assignment_types() {
+    let source = "
+        fn main() {
+            int a = 1;
+            double d = 1.5;
+            String s = \"\";
+
+            d = a;
+            d += a;
+            s += a;
+            a += d;
+            a = true;
+        }
+    ";
+
+    let errors = check_source(source).unwrap_err();
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+
+    assert_eq!(
+        messages,
+        vec![
+            "expected int but found double",
+            "expected int but found bool"
+        ]
+    );
+}
+
+#[test]
+fn test_global_order() {
+    // a global is only visible after its declaration
+    let source = "
+        int a = b;
+        int b = 1;
+
+        fn f() > int {
+            return c;
+        }
+
+        int c = 2;
+    ";
+
+    let errors = check_source(source).unwrap_err();
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+
+    assert_eq!(
+        messages,
+        vec!["undefined variable 'b'", "undefined variable 'c'"]
+    );
+
+    // functions are visible everywhere, reading `b` too early is only found at runtime
+    let source = "
+        int a = f();
+        int b = 2;
+
+        fn f() > int {
+            return b;
+        }
+    ";
+
+    assert!(check_source(source).is_ok());
+}
+
+#[test]
+fn test_missing_return() {
+    let source = "
+        fn sign(int a) > int {
+            if (a < 0) {
+                return -1;
+            }
+        }
+    ";
+
+    let errors = check_source(source).unwrap_err();
+
+    assert_eq!(
+        errors[0].message,
+        "function 'sign' does not return a value on every path"
+    );
+    assert_eq!(errors[0].code, "E0209");
+
+    // an endless loop can only be left by returning
+    let source = "
+        fn first(int[] values) > int {
+            while (true) {
+                return values[0];
+            }
+        }
+
+        fn next(int n) > int {
+            for (;;) {
+                if (n > 10) {
+                    return n;
+                }
+                n += 1;
+            }
+        }
+
+        fn maybe(bool b) > int {
+            while (b) {
+                return 1;
+            }
+        }
+    ";
+
+    let errors = check_source(source).unwrap_err();
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+
+    assert_eq!(
+        messages,
+        vec!["function 'maybe' does not return a value on every path"]
+    );
+}
+
+#[test]
+fn test_builtin_calls() {
+    let source = "
+        fn nothing() {}
+
+        fn main() {
+            out!(1, \"a\", 2.5);
+            out!(nothing());
+            int a = out!();
+            String s = in!(1);
+            err!();
+        }
+    ";
+
+    let errors = check_source(source).unwrap_err();
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+
+    assert_eq!(
+        messages,
+        vec![
+            "expected a value but found void",
+            "expected int but found void",
+            "'in!' expects 0 arguments but got 1",
+            "unknown builtin 'err!'"
+        ]
+    );
+}
+
+#[test]
+fn test_array_types() {
+    let source = "
+        class Point {}
+
+        fn main() {
+            int[] a = [1, 2];
+            double[] d = [1, 2.5];
+            int[][] nested = [[], a];
+            int[] empty = [];
+            String[] nulls = [null];
+            int[][] nested_empty = [[], [[]][0]];
+            Point[][] points = [[null], []];
+
+            a[0] = 1.5;
+            a[true] = 1;
+            int b = a[0][1];
+            String[] s = [1, \"a\"];
+            d = a;
+            double[] widened = [1, 2];
+            int[] ints = [null];
+
+            for x in a {
+                x += 1;
+            }
+            for x in b {}
+        }
+    ";
+
+    let errors = check_source(source).unwrap_err();
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+
+    assert_eq!(
+        messages,
+        vec![
+            "expected int but found double",
+            "expected int index but found bool",
+            "expected an array but found int",
+            "expected int but found String",
+            "expected double[] but found int[]",
+            "expected double[] but found int[]",
+            "expected int[] but found null[]",
+            "expected an array but found int"
+        ]
+    );
+}
@@ -0,0 +1,513 @@
+use crate::lexer::token::TokenType;
+use crate::lexer::trivia::{SyntaxTokens, Trivia, TriviaKind};
+
+/// how `rawk fmt` lays out the code
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// spaces per level of indentation
+    pub indent: usize,
+    /// lines longer than this are wrapped at the commas of a list, if they have one
+    pub line_length: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            indent: 4,
+            line_length: 100,
+        }
+    }
+}
+
+/// a token or an inline block comment
+#[derive(Debug)]
+struct Item {
+    text: String,
+    // None for comments
+    token: Option<TokenType>,
+    space_before: bool,
+}
+
+#[derive(Debug, Default)]
+struct Line {
+    indent: usize,
+    items: Vec<Item>,
+    // a line comment at the end, or a comment on its own line
+    comment: Option<String>,
+    blank_before: bool,
+}
+
+/// formats the tokens of a program which lexes and parses without errors,
+/// only the tokens, the comments and blank lines of the source are kept,
+/// so formatting the output again doesn't change it
+pub fn format(tokens: &SyntaxTokens, config: &Config) -> String {
+    let mut formatter = Formatter {
+        lines: Vec::new(),
+        line: Line::default(),
+        depth: 0,
+        brackets: 0,
+        newlines: 0,
+        line_break: true,
+        in_statement: false,
+        blank_line: false,
+        previous: None,
+    };
+
+    for token in &tokens.tokens {
+        formatter.leading(&token.leading);
+        formatter.token(token.token.token_type, &token.text);
+        formatter.trailing(&token.trailing);
+    }
+    formatter.leading(&tokens.end);
+    formatter.flush();
+
+    let mut output = String::new();
+    for line in &formatter.lines {
+        if line.blank_before {
+            output.push('\n');
+        }
+        render(line, config, &mut output);
+    }
+
+    output
+}
+
+struct Formatter {
+    lines: Vec<Line>,
+    line: Line,
+    // open braces
+    depth: usize,
+    // open parentheses and brackets, a ';' inside of them doesn't end the line
+    brackets: usize,
+    // line breaks in the source since the last token or comment
+    newlines: usize,
+    // the next token starts a new line
+    line_break: bool,
+    // a statement continues on the next line, which is indented once more
+    in_statement: bool,
+    // forced between top-level declarations
+    blank_line: bool,
+    // the last token and whether it was a unary operator
+    previous: Option<(TokenType, bool)>,
+}
+
+impl Formatter {
+    /// trivia after a line break, so its comments are on their own lines
+    fn leading(&mut self, trivia: &[Trivia]) {
+        for t in trivia {
+            match t.kind {
+                TriviaKind::Newline => self.newlines += 1,
+                TriviaKind::LineComment | TriviaKind::BlockComment => {
+                    self.start_line(false);
+                    self.line.comment = Some(t.text.clone());
+                    self.newlines = 0;
+                    self.line_break = true;
+                }
+                TriviaKind::Whitespace | TriviaKind::Skipped => {}
+            }
+        }
+    }
+
+    /// trivia on the same line as the token before
+    fn trailing(&mut self, trivia: &[Trivia]) {
+        for t in trivia {
+            match t.kind {
+                TriviaKind::Newline => self.newlines += 1,
+                TriviaKind::LineComment => {
+                    self.line.comment = Some(t.text.clone());
+                    self.line_break = true;
+                }
+                TriviaKind::BlockComment => {
+                    let after_bracket = self.previous.is_some_and(|(previous, _)| {
+                        matches!(previous, TokenType::Leftparen | TokenType::Leftbrack)
+                    });
+
+                    self.line.items.push(Item {
+                        text: t.text.clone(),
+                        token: None,
+                        space_before: !after_bracket,
+                    });
+                }
+                TriviaKind::Whitespace | TriviaKind::Skipped => {}
+            }
+        }
+    }
+
+    fn token(&mut self, token_type: TokenType, text: &str) {
+        let empty_block = token_type == TokenType::Rightcurl
+            && self.line.comment.is_none()
+            && self
+                .line
+                .items
+                .last()
+                .is_some_and(|item| item.token == Some(TokenType::Leftcurl));
+
+        if token_type == TokenType::Rightcurl {
+            self.depth = self.depth.saturating_sub(1);
+        }
+
+        let joined = match token_type {
+            TokenType::Rightcurl => empty_block,
+            // `} else {`
+            TokenType::Else => {
+                self.line.comment.is_none()
+                    && self
+                        .previous
+                        .is_some_and(|(previous, _)| previous == TokenType::Rightcurl)
+            }
+            TokenType::DocComment => false,
+            _ => !self.line_break,
+        };
+        if !joined {
+            self.start_line(token_type == TokenType::Rightcurl);
+        }
+
+        let unary = matches!(token_type, TokenType::Minus | TokenType::Bang)
+            && !self
+                .previous
+                .is_some_and(|(previous, _)| ends_operand(previous));
+        let space_before = match self.line.items.last() {
+            Some(item) => item.token.is_none() || self.space_before(token_type),
+            None => false,
+        };
+
+        self.line.items.push(Item {
+            text: text.to_string(),
+            token: Some(token_type),
+            space_before,
+        });
+        self.previous = Some((token_type, unary));
+        self.newlines = 0;
+
+        match token_type {
+            TokenType::Leftparen | TokenType::Leftbrack => self.brackets += 1,
+            TokenType::Rightparen | TokenType::Rightbrack => {
+                self.brackets = self.brackets.saturating_sub(1)
+            }
+            _ => {}
+        }
+
+        let ends_line = match token_type {
+            TokenType::Leftcurl => {
+                self.depth += 1;
+                true
+            }
+            TokenType::Rightcurl => {
+                self.blank_line = self.depth == 0;
+                true
+            }
+            TokenType::Semicolon => self.brackets == 0,
+            TokenType::DocComment => true,
+            _ => false,
+        };
+        self.line_break = ends_line;
+        self.in_statement = !ends_line;
+    }
+
+    fn space_before(&self, next: TokenType) -> bool {
+        let (previous, unary) = match self.previous {
+            Some(previous) => previous,
+            None => return false,
+        };
+
+        match (previous, next) {
+            (
+                _,
+                TokenType::Comma
+                | TokenType::Semicolon
+                | TokenType::Rightparen
+                | TokenType::Rightbrack
+                | TokenType::Dot,
+            ) => false,
+            (TokenType::Leftparen | TokenType::Leftbrack | TokenType::Dot, _) => false,
+            _ if unary => false,
+            // the `!` of a builtin like `out!`, `in` is a keyword
+            (TokenType::Identifier | TokenType::In, TokenType::Bang) => false,
+            // calls, indices and array types
+            (TokenType::Identifier | TokenType::Bang, TokenType::Leftparen) => false,
+            (
+                TokenType::Identifier | TokenType::Rightbrack | TokenType::Rightparen,
+                TokenType::Leftbrack,
+            ) => false,
+            (TokenType::Leftcurl, TokenType::Rightcurl) => false,
+            _ => true,
+        }
+    }
+
+    fn start_line(&mut self, closes_block: bool) {
+        self.flush();
+
+        let first_in_block = self.lines.last().is_some_and(|line| {
+            line.items
+                .last()
+                .is_some_and(|item| item.token == Some(TokenType::Leftcurl))
+        });
+
+        // blank lines of the source are kept, but never more than one
+        self.line.blank_before = (self.newlines >= 2 || self.blank_line)
+            && !self.lines.is_empty()
+            && !self.in_statement
+            && !first_in_block
+            && !closes_block;
+        self.line.indent = self.depth + self.in_statement as usize;
+        self.blank_line = false;
+        self.line_break = false;
+    }
+
+    fn flush(&mut self) {
+        if !self.line.items.is_empty() || self.line.comment.is_some() {
+            self.lines.push(std::mem::take(&mut self.line));
+        }
+    }
+}
+
+/// tokens after which a `-` or `!` is a binary operator
+fn ends_operand(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Identifier
+            | TokenType::String
+            | TokenType::Char
+            | TokenType::Int
+            | TokenType::Double
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Null
+            | TokenType::This
+            | TokenType::Rightparen
+            | TokenType::Rightbrack
+    )
+}
+
+fn render(line: &Line, config: &Config, output: &mut String) {
+    let mut rendered = Vec::new();
+    wrap(&line.items, line.indent, config, &mut rendered);
+
+    if let Some(comment) = &line.comment {
+        match rendered.last_mut() {
+            Some((_, text)) => {
+                text.push(' ');
+                text.push_str(comment);
+            }
+            None => rendered.push((line.indent, comment.clone())),
+        }
+    }
+
+    for (indent, text) in rendered {
+        output.push_str(&" ".repeat(indent * config.indent));
+        output.push_str(&text);
+        output.push('\n');
+    }
+}
+
+/// splits a line which is too long at the commas of its largest list,
+/// every element goes on its own line and is wrapped again if needed
+fn wrap(items: &[Item], indent: usize, config: &Config, output: &mut Vec<(usize, String)>) {
+    if items.is_empty() {
+        return;
+    }
+
+    let text = join(items);
+    if indent * config.indent + text.chars().count() <= config.line_length {
+        output.push((indent, text));
+        return;
+    }
+
+    let (open, close) = match widest_list(items) {
+        Some(list) => list,
+        None => {
+            output.push((indent, text));
+            return;
+        }
+    };
+
+    output.push((indent, join(&items[..=open])));
+
+    let mut depth = 0;
+    let mut start = open + 1;
+    for i in open + 1..close {
+        match items[i].token {
+            Some(TokenType::Leftparen | TokenType::Leftbrack) => depth += 1,
+            Some(TokenType::Rightparen | TokenType::Rightbrack) => depth -= 1,
+            Some(TokenType::Comma) if depth == 0 => {
+                wrap(&items[start..=i], indent + 1, config, output);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    wrap(&items[start..close], indent + 1, config, output);
+
+    wrap(&items[close..], indent, config, output);
+}
+
+/// the outermost bracketed list with the most items, preferring lists with commas
+fn widest_list(items: &[Item]) -> Option<(usize, usize)> {
+    let mut lists = Vec::new();
+    let mut depth = 0;
+    let mut open = 0;
+    let mut commas = false;
+
+    for (i, item) in items.iter().enumerate() {
+        match item.token {
+            Some(TokenType::Leftparen | TokenType::Leftbrack) => {
+                if depth == 0 {
+                    open = i;
+                    commas = false;
+                }
+                depth += 1;
+            }
+            Some(TokenType::Rightparen | TokenType::Rightbrack) if depth > 0 => {
+                depth -= 1;
+                if depth == 0 && i > open + 1 {
+                    lists.push((commas, i - open, open, i));
+                }
+            }
+            Some(TokenType::Comma) if depth > 0 => commas = true,
+            _ => {}
+        }
+    }
+
+    lists
+        .into_iter()
+        .filter(|(commas, ..)| *commas)
+        .max_by_key(|(_, len, open, _)| (*len, std::cmp::Reverse(*open)))
+        .map(|(_, _, open, close)| (open, close))
+}
+
+fn join(items: &[Item]) -> String {
+    let mut text = String::new();
+
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 && item.space_before {
+            text.push(' ');
+        }
+        text.push_str(&item.text);
+    }
+
+    text
+}
+
+#[cfg(test)]
+fn format_source(source: &str, config: &Config) -> String {
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    let (tokens, diagnostics) = Lexer::from_string(source.to_string()).lex_lossless();
+    assert!(diagnostics.is_empty());
+    Parser::new(tokens.parser_tokens()).parse().unwrap();
+
+    let formatted = format(&tokens, config);
+    // formatting is idempotent
+    let (again, _) = Lexer::from_string(formatted.clone()).lex_lossless();
+    assert_eq!(format(&again, config), formatted);
+
+    formatted
+}
+
+#[test]
+fn test_format_program() {
+    let source = "
+/// a point
+class Point{int x;int y=-1;
+    Point(int x){this.x=x;}
+
+
+    fn length()>double{return (x*x+y**2)**0.5;}
+}
+fn main()
+{
+  int[] arr=[0,1,2,-3];
+  for num in arr
+  {
+    if(!(num%2==0)&&num> -1){out!(num,\" is odd\");}
+    else if (num < 0) { return; } else {}
+  }
+  for (int i = 0; i < len!(arr); i += 1) arr[i] = arr[i]-1;
+  String line = in ! ( );
+}";
+
+    let expected = "\
+/// a point
+class Point {
+    int x;
+    int y = -1;
+    Point(int x) {
+        this.x = x;
+    }
+
+    fn length() > double {
+        return (x * x + y ** 2) ** 0.5;
+    }
+}
+
+fn main() {
+    int[] arr = [0, 1, 2, -3];
+    for num in arr {
+        if (!(num % 2 == 0) && num > -1) {
+            out!(num, \" is odd\");
+        } else if (num < 0) {
+            return;
+        } else {}
+    }
+    for (int i = 0; i < len!(arr); i += 1) arr[i] = arr[i] - 1;
+    String line = in!();
+}
+";
+
+    assert_eq!(format_source(source, &Config::default()), expected);
+}
+
+#[test]
+fn test_format_keeps_comments() {
+    let source = "// header
+
+fn main() { // entry
+    /* before */ int a = 1; /* after */
+    out!(a, // first
+        2);
+
+    // dangling
+}
+// end
+";
+
+    let expected = "// header
+
+fn main() { // entry
+    /* before */
+    int a = 1; /* after */
+    out!(a, // first
+        2);
+
+    // dangling
+}
+
+// end
+";
+
+    assert_eq!(format_source(source, &Config::default()), expected);
+}
+
+#[test]
+fn test_format_wraps_long_lines() {
+    let source = "fn main() {
+    out!(\"first argument\", combine(\"second\", \"third argument\"), [1, 2, 3]);
+}";
+
+    let config = Config {
+        indent: 2,
+        line_length: 40,
+    };
+    let expected = "\
+fn main() {
+  out!(
+    \"first argument\",
+    combine(\"second\", \"third argument\"),
+    [1, 2, 3]
+  );
+}
+";
+
+    assert_eq!(format_source(source, &config), expected);
+}
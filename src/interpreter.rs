@@ -0,0 +1,1630 @@
+pub mod heap;
+pub mod value;
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use self::heap::{Array, GcStats, Handle, Heap, Instance, Object};
+use self::value::Value;
+use crate::builtins;
+use crate::lexer::token::{Token, TokenSpan, TokenType};
+use crate::parser::ast::{
+    ClassDecl, Decl, Entry, Expr, FunctionDecl, Program, Stmt, Type, VarDecl,
+};
+use crate::reporter::diagnostic::{Diagnostic, TraceFrame};
+
+pub(crate) const MAX_CALL_DEPTH: usize = 512;
+
+/// native stack the interpreter needs to reach `MAX_CALL_DEPTH` in a debug
+/// build, every call and every nested statement and expression recurses
+pub(crate) const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub code: &'static str,
+    pub message: String,
+    pub span: TokenSpan,
+    /// the calls the error unwound, innermost first
+    pub trace: Vec<TraceFrame>,
+}
+
+impl RuntimeError {
+    pub(crate) fn new(code: &'static str, message: String, token: &Token) -> Self {
+        RuntimeError::at(code, message, token.span)
+    }
+
+    pub(crate) fn at(code: &'static str, message: String, span: TokenSpan) -> Self {
+        RuntimeError {
+            code,
+            message,
+            span,
+            trace: Vec::new(),
+        }
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::error(self.code, self.message.clone(), self.span).with_trace(self.trace.clone())
+    }
+}
+
+/// result of executing a statement
+enum Flow {
+    Normal,
+    Return(Value),
+}
+
+/// a variable keeps its declared type, so assignments can be converted
+#[derive(Debug)]
+struct Variable {
+    ty: Type,
+    value: Value,
+}
+
+/// local variables of a single function invocation, one map per block
+#[derive(Debug)]
+struct Frame {
+    scopes: Vec<HashMap<String, Variable>>,
+    return_type: Type,
+    this: Option<Value>,
+}
+
+pub struct Interpreter {
+    functions: HashMap<String, Rc<FunctionDecl>>,
+    classes: HashMap<String, Rc<ClassDecl>>,
+    globals: HashMap<String, Variable>,
+    frames: Vec<Frame>,
+    heap: Heap,
+    // objects which are only referenced from the rust stack while evaluating an expression
+    temps: Vec<Handle>,
+    output: Box<dyn Write>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::with_output(Box::new(io::stdout()))
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Interpreter::default()
+    }
+
+    /// an interpreter which writes everything printed by `out!` to `output`
+    pub fn with_output(output: Box<dyn Write>) -> Self {
+        Interpreter {
+            functions: HashMap::new(),
+            classes: HashMap::new(),
+            globals: HashMap::new(),
+            frames: Vec::new(),
+            heap: Heap::new(),
+            temps: Vec::new(),
+            output,
+        }
+    }
+
+    /// declares all functions and globals and runs the `main` function
+    pub fn interpret(&mut self, program: &Program) -> Result<Value, RuntimeError> {
+        for decl in &program.declarations {
+            self.declare(decl);
+        }
+
+        for decl in &program.declarations {
+            if let Decl::Var(var) = decl {
+                self.define_global(var)?;
+            }
+        }
+
+        let main = match self.functions.get("main") {
+            Some(main) => main.clone(),
+            None => {
+                return Err(RuntimeError::at(
+                    "E0306",
+                    "no 'main' function found".to_string(),
+                    TokenSpan { start: 0, end: 0 },
+                ))
+            }
+        };
+
+        self.call_function(&main, Vec::new(), main.name.span, None)
+    }
+
+    /// runs an input of the REPL and returns the value of its final expression,
+    /// globals, functions and classes are kept for the next input
+    pub fn run_entries(&mut self, entries: &[Entry]) -> Result<Option<Value>, RuntimeError> {
+        for entry in entries {
+            if let Entry::Decl(decl) = entry {
+                self.declare(decl);
+            }
+        }
+
+        let mut result = None;
+        for entry in entries {
+            match entry {
+                Entry::Decl(Decl::Var(var)) => self.define_global(var)?,
+                Entry::Decl(_) => {}
+                Entry::Stmt(stmt) => {
+                    self.frames.push(Frame {
+                        scopes: vec![HashMap::new()],
+                        return_type: Type::Void,
+                        this: None,
+                    });
+                    let flow = self.execute(stmt);
+                    self.frames.pop();
+                    flow?;
+                }
+                Entry::Expr(expr) => result = Some(self.evaluate(expr)?),
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn declare(&mut self, decl: &Decl) {
+        match decl {
+            Decl::Function(function) => {
+                self.functions
+                    .insert(function.name.lexeme.clone(), function.clone());
+            }
+            Decl::Class(class) => {
+                self.classes
+                    .insert(class.name.lexeme.clone(), class.clone());
+            }
+            Decl::Var(_) => {}
+        }
+    }
+
+    fn define_global(&mut self, var: &VarDecl) -> Result<(), RuntimeError> {
+        let value = self.initial_value(var)?;
+        self.globals.insert(
+            var.name.lexeme.clone(),
+            Variable {
+                ty: var.ty.clone(),
+                value,
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn heap(&self) -> &Heap {
+        &self.heap
+    }
+
+    pub fn gc_stats(&mut self) -> &GcStats {
+        self.heap.stats()
+    }
+
+    fn call_function(
+        &mut self,
+        function: &FunctionDecl,
+        arguments: Vec<Value>,
+        span: TokenSpan,
+        this: Option<Value>,
+    ) -> Result<Value, RuntimeError> {
+        if arguments.len() != function.params.len() {
+            return Err(RuntimeError::at(
+                "E0212",
+                format!(
+                    "expected {} arguments but got {}",
+                    function.params.len(),
+                    arguments.len()
+                ),
+                span,
+            ));
+        }
+
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::at(
+                "E0305",
+                "stack overflow".to_string(),
+                span,
+            ));
+        }
+
+        let mut scope = HashMap::new();
+        for (param, value) in function.params.iter().zip(arguments) {
+            let value = Interpreter::coerce(&mut self.heap, value, &param.ty, span)?;
+            let ty = param.ty.clone();
+            scope.insert(param.name.lexeme.clone(), Variable { ty, value });
+        }
+
+        self.frames.push(Frame {
+            scopes: vec![scope],
+            return_type: function.return_type.clone(),
+            this,
+        });
+        let result = self.execute_block_statements(&function.body);
+        self.frames.pop();
+
+        match result {
+            Ok(Flow::Return(value)) => Ok(value),
+            Ok(Flow::Normal) => Ok(Value::Null),
+            Err(mut err) => {
+                err.trace.push(TraceFrame {
+                    function: function.name.lexeme.clone(),
+                    span,
+                });
+                Err(err)
+            }
+        }
+    }
+
+    fn execute(&mut self, stmt: &Stmt) -> Result<Flow, RuntimeError> {
+        match stmt {
+            Stmt::Expression(expr) => {
+                self.evaluate(expr)?;
+            }
+            Stmt::Var(var) => {
+                let value = self.initial_value(var)?;
+                self.define(&var.name.lexeme, var.ty.clone(), value);
+            }
+            Stmt::Block(statements) => return self.execute_block(statements),
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                if self.evaluate_condition(condition)? {
+                    return self.execute(then_branch);
+                } else if let Some(else_branch) = else_branch {
+                    return self.execute(else_branch);
+                }
+            }
+            Stmt::While { condition, body } => {
+                while self.evaluate_condition(condition)? {
+                    if let Flow::Return(value) = self.execute(body)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+            }
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+            } => {
+                // the initializer gets its own scope around the whole loop
+                self.begin_scope();
+                let result = self.execute_for(initializer, condition, increment, body);
+                self.end_scope();
+
+                return result;
+            }
+            Stmt::ForIn {
+                name,
+                iterable,
+                body,
+            } => {
+                let array = self.evaluate_array(iterable)?;
+
+                // the array has to stay alive even if the variable holding it is reassigned
+                self.temps.push(array);
+                self.begin_scope();
+                let result = self.execute_for_in(name, array, body);
+                self.end_scope();
+                self.temps.pop();
+
+                return result;
+            }
+            Stmt::Return { keyword, value } => {
+                let (value, span) = match value {
+                    Some(expr) => (self.evaluate(expr)?, expr.span()),
+                    None => (Value::Null, keyword.span),
+                };
+
+                let value = match self.frames.last() {
+                    Some(frame) => {
+                        Interpreter::coerce(&mut self.heap, value, &frame.return_type, span)?
+                    }
+                    None => value,
+                };
+
+                return Ok(Flow::Return(value));
+            }
+        }
+
+        Ok(Flow::Normal)
+    }
+
+    fn execute_for(
+        &mut self,
+        initializer: &Option<Box<Stmt>>,
+        condition: &Option<Expr>,
+        increment: &Option<Expr>,
+        body: &Stmt,
+    ) -> Result<Flow, RuntimeError> {
+        if let Some(initializer) = initializer {
+            self.execute(initializer)?;
+        }
+
+        loop {
+            if let Some(condition) = condition {
+                if !self.evaluate_condition(condition)? {
+                    break;
+                }
+            }
+
+            if let Flow::Return(value) = self.execute(body)? {
+                return Ok(Flow::Return(value));
+            }
+
+            if let Some(increment) = increment {
+                self.evaluate(increment)?;
+            }
+        }
+
+        Ok(Flow::Normal)
+    }
+
+    fn execute_for_in(
+        &mut self,
+        name: &Token,
+        array: Handle,
+        body: &Stmt,
+    ) -> Result<Flow, RuntimeError> {
+        let ty = self.heap.array(array).ty.clone();
+
+        // the body can assign to the elements, so they are read one at a time
+        let mut i = 0;
+        while let Some(element) = self.heap.array(array).elements.get(i).cloned() {
+            self.define(&name.lexeme, ty.clone(), element);
+
+            if let Flow::Return(value) = self.execute(body)? {
+                return Ok(Flow::Return(value));
+            }
+
+            i += 1;
+        }
+
+        Ok(Flow::Normal)
+    }
+
+    fn execute_block(&mut self, statements: &[Stmt]) -> Result<Flow, RuntimeError> {
+        self.begin_scope();
+        let result = self.execute_block_statements(statements);
+        self.end_scope();
+
+        result
+    }
+
+    /// executes statements in the current scope
+    fn execute_block_statements(&mut self, statements: &[Stmt]) -> Result<Flow, RuntimeError> {
+        for stmt in statements {
+            if let Flow::Return(value) = self.execute(stmt)? {
+                return Ok(Flow::Return(value));
+            }
+        }
+
+        Ok(Flow::Normal)
+    }
+
+    fn initial_value(&mut self, var: &VarDecl) -> Result<Value, RuntimeError> {
+        match &var.initializer {
+            Some(expr) => {
+                let value = self.evaluate(expr)?;
+                Interpreter::coerce(&mut self.heap, value, &var.ty, expr.span())
+            }
+            None => Ok(Interpreter::default_value(&var.ty)),
+        }
+    }
+
+    pub(crate) fn coerce(
+        heap: &mut Heap,
+        value: Value,
+        ty: &Type,
+        span: TokenSpan,
+    ) -> Result<Value, RuntimeError> {
+        let type_name = value.type_name(heap);
+
+        value.coerce(ty, heap).ok_or_else(|| {
+            RuntimeError::at(
+                "E0206",
+                format!("expected {} but found {}", ty, type_name),
+                span,
+            )
+        })
+    }
+
+    pub(crate) fn default_value(ty: &Type) -> Value {
+        match ty {
+            Type::Int => Value::Int(0),
+            Type::Double => Value::Double(0.0),
+            Type::Bool => Value::Bool(false),
+            Type::Char => Value::Char('\0'),
+            Type::String | Type::Class(_) | Type::Array(_) | Type::Null | Type::Void => Value::Null,
+        }
+    }
+
+    fn evaluate_bool(&mut self, expr: &Expr, operator: &Token) -> Result<bool, RuntimeError> {
+        match self.evaluate(expr)? {
+            Value::Bool(b) => Ok(b),
+            value => Err(RuntimeError::at(
+                "E0207",
+                format!(
+                    "cannot apply '{}' to {}",
+                    operator.lexeme,
+                    value.type_name(&self.heap)
+                ),
+                expr.span(),
+            )),
+        }
+    }
+
+    fn evaluate_condition(&mut self, condition: &Expr) -> Result<bool, RuntimeError> {
+        match self.evaluate(condition)? {
+            Value::Bool(b) => Ok(b),
+            value => Err(RuntimeError::at(
+                "E0206",
+                format!(
+                    "expected bool condition but found {}",
+                    value.type_name(&self.heap)
+                ),
+                condition.span(),
+            )),
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.scopes.push(HashMap::new());
+        }
+    }
+
+    fn end_scope(&mut self) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.scopes.pop();
+        }
+    }
+
+    fn define(&mut self, name: &str, ty: Type, value: Value) {
+        let variable = Variable { ty, value };
+
+        match self.frames.last_mut().and_then(|f| f.scopes.last_mut()) {
+            Some(scope) => scope.insert(name.to_string(), variable),
+            None => self.globals.insert(name.to_string(), variable),
+        };
+    }
+
+    fn lookup(&self, name: &Token) -> Result<Value, RuntimeError> {
+        if let Some(frame) = self.frames.last() {
+            for scope in frame.scopes.iter().rev() {
+                if let Some(variable) = scope.get(&name.lexeme) {
+                    return Ok(variable.value.clone());
+                }
+            }
+        }
+
+        match self.globals.get(&name.lexeme) {
+            Some(variable) => Ok(variable.value.clone()),
+            None => Err(RuntimeError::new(
+                "E0202",
+                format!("undefined variable '{}'", name.lexeme),
+                name,
+            )),
+        }
+    }
+
+    fn assign(&mut self, name: &Token, value: Value, span: TokenSpan) -> Result<(), RuntimeError> {
+        let local = self.frames.last_mut().and_then(|frame| {
+            frame
+                .scopes
+                .iter_mut()
+                .rev()
+                .find_map(|scope| scope.get_mut(&name.lexeme))
+        });
+
+        let variable = match local {
+            Some(variable) => variable,
+            None => match self.globals.get_mut(&name.lexeme) {
+                Some(variable) => variable,
+                None => {
+                    return Err(RuntimeError::new(
+                        "E0202",
+                        format!("undefined variable '{}'", name.lexeme),
+                        name,
+                    ))
+                }
+            },
+        };
+
+        variable.value = Interpreter::coerce(&mut self.heap, value, &variable.ty, span)?;
+
+        Ok(())
+    }
+
+    /// applies the operator of a compound assignment like `+=`,
+    /// a plain `=` just takes the new value
+    pub(crate) fn compound(
+        heap: &Heap,
+        current: Value,
+        operator: &Token,
+        value: Value,
+    ) -> Result<Value, RuntimeError> {
+        match operator.token_type.compound_operator() {
+            Some(token_type) => {
+                let operator = Token {
+                    token_type,
+                    lexeme: operator.lexeme.clone(),
+                    span: operator.span,
+                };
+
+                Interpreter::binary(heap, current, &operator, value)
+            }
+            None => Ok(value),
+        }
+    }
+
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+        match expr {
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                let mark = self.temps.len();
+                let left = self.evaluate(left)?;
+                self.push_temp(&left);
+                let right = self.evaluate(right);
+                self.temps.truncate(mark);
+
+                Interpreter::binary(&self.heap, left, operator, right?)
+            }
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.evaluate_bool(left, operator)?;
+
+                // the right side is only evaluated if it decides the result
+                let short_circuit = match operator.token_type {
+                    TokenType::Or => left,
+                    _ => !left,
+                };
+
+                if short_circuit {
+                    Ok(Value::Bool(left))
+                } else {
+                    self.evaluate_bool(right, operator).map(Value::Bool)
+                }
+            }
+            Expr::Unary { operator, right } => {
+                let right = self.evaluate(right)?;
+
+                Interpreter::unary(&self.heap, operator, right)
+            }
+            Expr::Grouping(expr) => self.evaluate(expr),
+            Expr::Variable(name) => self.lookup(name),
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+            } => self.call(callee, paren, arguments),
+            Expr::Get { object, name } => {
+                let instance = self.evaluate_instance(object)?;
+                let instance = self.heap.instance(instance);
+
+                match instance.fields.get(&name.lexeme) {
+                    Some(value) => Ok(value.clone()),
+                    None => Err(RuntimeError::new(
+                        "E0204",
+                        format!(
+                            "'{}' has no field '{}'",
+                            instance.class.name.lexeme, name.lexeme
+                        ),
+                        name,
+                    )),
+                }
+            }
+            Expr::Assign {
+                name,
+                operator,
+                value,
+            } => {
+                // like in Java the target is read before the value is evaluated
+                let current = match operator.token_type {
+                    TokenType::Equal => None,
+                    _ => Some(self.lookup(name)?),
+                };
+                let value = self.evaluate(value)?;
+                let value = match current {
+                    Some(current) => Interpreter::compound(&self.heap, current, operator, value)?,
+                    None => value,
+                };
+
+                self.assign(name, value, expr.span())?;
+
+                self.lookup(name)
+            }
+            Expr::Set {
+                object,
+                name,
+                operator,
+                value,
+            } => {
+                let mark = self.temps.len();
+                let instance = self.evaluate_instance(object)?;
+                let current = match operator.token_type {
+                    TokenType::Equal => None,
+                    _ => {
+                        let fields = &self.heap.instance(instance).fields;
+                        Some(fields.get(&name.lexeme).cloned().unwrap_or(Value::Null))
+                    }
+                };
+                self.temps.push(instance);
+                let value = self.evaluate(value);
+                self.temps.truncate(mark);
+                let value = value?;
+
+                let class = self.heap.instance(instance).class.clone();
+                let ty = match class.find_field(&name.lexeme) {
+                    Some(field) => field.ty.clone(),
+                    None => {
+                        return Err(RuntimeError::new(
+                            "E0204",
+                            format!("'{}' has no field '{}'", class.name.lexeme, name.lexeme),
+                            name,
+                        ))
+                    }
+                };
+
+                let value = match current {
+                    Some(current) => Interpreter::compound(&self.heap, current, operator, value)?,
+                    None => value,
+                };
+
+                let value = Interpreter::coerce(&mut self.heap, value, &ty, expr.span())?;
+                self.heap
+                    .instance_mut(instance)
+                    .fields
+                    .insert(name.lexeme.clone(), value.clone());
+
+                Ok(value)
+            }
+            Expr::Index { object, index, .. } => {
+                let (array, i) = self.evaluate_index(object, index)?;
+
+                Ok(self.heap.array(array).elements[i].clone())
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                operator,
+                value,
+            } => {
+                let mark = self.temps.len();
+                let result = self.evaluate_index(object, index).and_then(|(array, i)| {
+                    let current = match operator.token_type {
+                        TokenType::Equal => None,
+                        _ => Some(self.heap.array(array).elements[i].clone()),
+                    };
+                    self.temps.push(array);
+                    Ok((array, i, current, self.evaluate(value)?))
+                });
+                self.temps.truncate(mark);
+                let (array, i, current, value) = result?;
+
+                let value = match current {
+                    Some(current) => Interpreter::compound(&self.heap, current, operator, value)?,
+                    None => value,
+                };
+
+                let ty = self.heap.array(array).ty.clone();
+                let value = Interpreter::coerce(&mut self.heap, value, &ty, expr.span())?;
+
+                self.heap.array_mut(array).elements[i] = value.clone();
+
+                Ok(value)
+            }
+            Expr::Array(elements, span) => {
+                let mark = self.temps.len();
+                let result = self.evaluate_arguments(elements).and_then(|values| {
+                    let array = Interpreter::array_literal(&mut self.heap, values, *span)?;
+                    Ok(Value::Array(self.allocate(Object::Array(array))))
+                });
+                self.temps.truncate(mark);
+
+                result
+            }
+            Expr::Builtin {
+                name,
+                paren: _,
+                arguments,
+            } => {
+                let builtin = match builtins::lookup(&name.lexeme) {
+                    Some(builtin) => builtin,
+                    None => {
+                        return Err(RuntimeError::new(
+                            "E0205",
+                            format!("unknown builtin '{}!'", name.lexeme),
+                            name,
+                        ))
+                    }
+                };
+
+                let mark = self.temps.len();
+                let arguments = self.evaluate_arguments(arguments);
+                self.temps.truncate(mark);
+
+                (builtin.function)(self.output.as_mut(), &self.heap, arguments?)
+                    .map_err(|message| RuntimeError::at("E0206", message, expr.span()))
+            }
+            Expr::This(keyword) => match self.frames.last().and_then(|f| f.this.clone()) {
+                Some(this) => Ok(this),
+                None => Err(RuntimeError::new(
+                    "E0211",
+                    "'this' used outside of a method".to_string(),
+                    keyword,
+                )),
+            },
+            Expr::New {
+                keyword,
+                class,
+                paren,
+                arguments,
+            } => {
+                let mark = self.temps.len();
+                let result = self.evaluate_arguments(arguments).and_then(|values| {
+                    self.instantiate(class, values, keyword.span.to(paren.span))
+                });
+                self.temps.truncate(mark);
+
+                result
+            }
+            Expr::LiteralTrue(_) => Ok(Value::Bool(true)),
+            Expr::LiteralFalse(_) => Ok(Value::Bool(false)),
+            Expr::LiteralNull(_) => Ok(Value::Null),
+            Expr::LiteralInt(i, _) => Ok(Value::Int(*i)),
+            Expr::LiteralDouble(d, _) => Ok(Value::Double(*d)),
+            Expr::LiteralString(s, _) => Ok(Value::String(s.clone())),
+            Expr::LiteralChar(c, _) => Ok(Value::Char(*c)),
+        }
+    }
+
+    fn call(
+        &mut self,
+        callee: &Expr,
+        paren: &Token,
+        arguments: &[Expr],
+    ) -> Result<Value, RuntimeError> {
+        let span = callee.span().to(paren.span);
+
+        let (function, this) = match callee {
+            Expr::Variable(name) => match self.functions.get(&name.lexeme) {
+                Some(function) => (function.clone(), None),
+                None => {
+                    return Err(RuntimeError::new(
+                        "E0202",
+                        format!("undefined function '{}'", name.lexeme),
+                        name,
+                    ))
+                }
+            },
+            Expr::Get { object, name } => {
+                let instance = self.evaluate_instance(object)?;
+                let class = self.heap.instance(instance).class.clone();
+
+                match class.find_method(&name.lexeme) {
+                    Some(method) => (method.clone(), Some(Value::Object(instance))),
+                    None => {
+                        return Err(RuntimeError::new(
+                            "E0204",
+                            format!("'{}' has no method '{}'", class.name.lexeme, name.lexeme),
+                            name,
+                        ))
+                    }
+                }
+            }
+            _ => {
+                return Err(RuntimeError::at(
+                    "E0213",
+                    "expression is not callable".to_string(),
+                    callee.span(),
+                ))
+            }
+        };
+
+        let mark = self.temps.len();
+        if let Some(this) = &this {
+            self.push_temp(this);
+        }
+        let result = self
+            .evaluate_arguments(arguments)
+            .and_then(|values| self.call_function(&function, values, span, this));
+        self.temps.truncate(mark);
+
+        result
+    }
+
+    /// evaluates the arguments of a call, they are kept alive as temporaries
+    /// until the caller truncates `temps`
+    fn evaluate_arguments(&mut self, arguments: &[Expr]) -> Result<Vec<Value>, RuntimeError> {
+        let mut values = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            let value = self.evaluate(argument)?;
+            self.push_temp(&value);
+            values.push(value);
+        }
+
+        Ok(values)
+    }
+
+    /// builds the array of a literal, the element type is the common type of all values
+    pub(crate) fn array_literal(
+        heap: &mut Heap,
+        values: Vec<Value>,
+        span: TokenSpan,
+    ) -> Result<Array, RuntimeError> {
+        let mut ty = values.first().map_or(Type::Void, |v| v.ty(heap));
+        for value in &values {
+            ty = ty.common(&value.ty(heap)).ok_or_else(|| {
+                RuntimeError::at(
+                    "E0206",
+                    format!("expected {} but found {}", ty, value.type_name(heap)),
+                    span,
+                )
+            })?;
+        }
+
+        let mut elements = Vec::with_capacity(values.len());
+        for value in values {
+            elements.push(Interpreter::coerce(heap, value, &ty, span)?);
+        }
+
+        Ok(Array { ty, elements })
+    }
+
+    fn evaluate_array(&mut self, expr: &Expr) -> Result<Handle, RuntimeError> {
+        match self.evaluate(expr)? {
+            Value::Array(array) => Ok(array),
+            value => Err(RuntimeError::at(
+                "E0206",
+                format!(
+                    "expected an array but found {}",
+                    value.type_name(&self.heap)
+                ),
+                expr.span(),
+            )),
+        }
+    }
+
+    /// evaluates an indexed array and checks that the index is in bounds
+    fn evaluate_index(
+        &mut self,
+        object: &Expr,
+        index: &Expr,
+    ) -> Result<(Handle, usize), RuntimeError> {
+        let array = self.evaluate_array(object)?;
+
+        let mark = self.temps.len();
+        self.temps.push(array);
+        let i = self.evaluate(index);
+        self.temps.truncate(mark);
+
+        let i = match i? {
+            Value::Int(i) => i,
+            value => {
+                return Err(RuntimeError::at(
+                    "E0206",
+                    format!(
+                        "expected int index but found {}",
+                        value.type_name(&self.heap)
+                    ),
+                    index.span(),
+                ))
+            }
+        };
+
+        let len = self.heap.array(array).elements.len();
+        match usize::try_from(i) {
+            Ok(i) if i < len => Ok((array, i)),
+            _ => Err(RuntimeError::at(
+                "E0304",
+                format!("index {} out of bounds for length {}", i, len),
+                index.span(),
+            )),
+        }
+    }
+
+    fn evaluate_instance(&mut self, object: &Expr) -> Result<Handle, RuntimeError> {
+        match self.evaluate(object)? {
+            Value::Object(instance) => Ok(instance),
+            value => Err(RuntimeError::at(
+                "E0206",
+                format!(
+                    "expected an object but found {}",
+                    value.type_name(&self.heap)
+                ),
+                object.span(),
+            )),
+        }
+    }
+
+    /// creates a new instance, initializes its fields and runs the constructor
+    fn instantiate(
+        &mut self,
+        class: &Token,
+        arguments: Vec<Value>,
+        span: TokenSpan,
+    ) -> Result<Value, RuntimeError> {
+        let class = match self.classes.get(&class.lexeme) {
+            Some(class) => class.clone(),
+            None => {
+                return Err(RuntimeError::new(
+                    "E0203",
+                    format!("undefined class '{}'", class.lexeme),
+                    class,
+                ))
+            }
+        };
+
+        let instance = Value::Object(self.allocate(Object::Instance(Instance {
+            class: class.clone(),
+            fields: HashMap::new(),
+        })));
+
+        // field initializers run with `this` bound to the new instance,
+        // which also keeps it alive during collections
+        self.frames.push(Frame {
+            scopes: vec![HashMap::new()],
+            return_type: Type::Void,
+            this: Some(instance.clone()),
+        });
+        let result = self.initialize_fields(&class, &instance);
+        self.frames.pop();
+        result?;
+
+        match &class.constructor {
+            Some(constructor) => {
+                self.call_function(constructor, arguments, span, Some(instance.clone()))?;
+            }
+            None if !arguments.is_empty() => {
+                return Err(RuntimeError::at(
+                    "E0212",
+                    format!(
+                        "'{}' has no constructor taking {} arguments",
+                        class.name.lexeme,
+                        arguments.len()
+                    ),
+                    span,
+                ))
+            }
+            None => {}
+        }
+
+        Ok(instance)
+    }
+
+    fn initialize_fields(
+        &mut self,
+        class: &ClassDecl,
+        instance: &Value,
+    ) -> Result<(), RuntimeError> {
+        let handle = match instance {
+            Value::Object(handle) => *handle,
+            _ => unreachable!(),
+        };
+
+        // every field is stored right away, so collections during later initializers see it
+        for field in &class.fields {
+            let value = self.initial_value(field)?;
+            self.heap
+                .instance_mut(handle)
+                .fields
+                .insert(field.name.lexeme.clone(), value);
+        }
+
+        Ok(())
+    }
+
+    fn allocate(&mut self, object: Object) -> Handle {
+        let handle = self.heap.allocate(object);
+
+        if self.heap.should_collect() {
+            self.temps.push(handle);
+            self.collect_garbage();
+            self.temps.pop();
+        }
+
+        handle
+    }
+
+    /// keeps an object alive while it is only referenced from the rust stack
+    fn push_temp(&mut self, value: &Value) {
+        if let Some(handle) = value.handle() {
+            self.temps.push(handle);
+        }
+    }
+
+    /// the roots are all variables of the call stack, `this` of every method and the globals
+    fn collect_garbage(&mut self) {
+        let locals = self.frames.iter().flat_map(|frame| {
+            let variables = frame.scopes.iter().flat_map(|scope| scope.values());
+            variables.map(|v| &v.value).chain(&frame.this)
+        });
+        let globals = self.globals.values().map(|v| &v.value);
+        let roots = locals.chain(globals).filter_map(Value::handle);
+
+        self.heap.collect(roots.chain(self.temps.iter().copied()));
+    }
+
+    pub(crate) fn unary(
+        heap: &Heap,
+        operator: &Token,
+        right: Value,
+    ) -> Result<Value, RuntimeError> {
+        match (operator.token_type, right) {
+            (TokenType::Minus, Value::Int(i)) => i.checked_neg().map(Value::Int).ok_or_else(|| {
+                RuntimeError::new("E0302", "integer overflow".to_string(), operator)
+            }),
+            (TokenType::Minus, Value::Double(d)) => Ok(Value::Double(-d)),
+            (TokenType::Bang, Value::Bool(b)) => Ok(Value::Bool(!b)),
+            (_, right) => Err(RuntimeError::new(
+                "E0207",
+                format!(
+                    "cannot apply unary '{}' to {}",
+                    operator.lexeme,
+                    right.type_name(heap)
+                ),
+                operator,
+            )),
+        }
+    }
+
+    pub(crate) fn binary(
+        heap: &Heap,
+        left: Value,
+        operator: &Token,
+        right: Value,
+    ) -> Result<Value, RuntimeError> {
+        match operator.token_type {
+            TokenType::Equalequal => {
+                Interpreter::equals(heap, &left, operator, &right).map(Value::Bool)
+            }
+            TokenType::Bangequal => {
+                Interpreter::equals(heap, &left, operator, &right).map(|eq| Value::Bool(!eq))
+            }
+            TokenType::Plus => match (left, right) {
+                (Value::String(l), r) => Ok(Value::String(format!("{}{}", l, r.display(heap)))),
+                (l, Value::String(r)) => Ok(Value::String(format!("{}{}", l.display(heap), r))),
+                (l, r) => Interpreter::arithmetic(heap, l, operator, r),
+            },
+            TokenType::Minus
+            | TokenType::Star
+            | TokenType::Slash
+            | TokenType::Remainder
+            | TokenType::Power => Interpreter::arithmetic(heap, left, operator, right),
+            TokenType::Greater
+            | TokenType::Greaterequal
+            | TokenType::Less
+            | TokenType::Lessequal => Interpreter::comparison(heap, left, operator, right),
+            _ => Err(RuntimeError::new(
+                "E0207",
+                format!("unknown binary operator '{}'", operator.lexeme),
+                operator,
+            )),
+        }
+    }
+
+    fn arithmetic(
+        heap: &Heap,
+        left: Value,
+        operator: &Token,
+        right: Value,
+    ) -> Result<Value, RuntimeError> {
+        match (left, right) {
+            (Value::Int(l), Value::Int(r)) => Interpreter::int_arithmetic(l, operator, r),
+            (Value::Int(l), Value::Double(r)) => {
+                Interpreter::double_arithmetic(l as f64, operator, r)
+            }
+            (Value::Double(l), Value::Int(r)) => {
+                Interpreter::double_arithmetic(l, operator, r as f64)
+            }
+            (Value::Double(l), Value::Double(r)) => Interpreter::double_arithmetic(l, operator, r),
+            (l, r) => Err(RuntimeError::new(
+                "E0207",
+                format!(
+                    "cannot apply '{}' to {} and {}",
+                    operator.lexeme,
+                    l.type_name(heap),
+                    r.type_name(heap)
+                ),
+                operator,
+            )),
+        }
+    }
+
+    fn int_arithmetic(left: i64, operator: &Token, right: i64) -> Result<Value, RuntimeError> {
+        if right == 0 && matches!(operator.token_type, TokenType::Slash | TokenType::Remainder) {
+            return Err(RuntimeError::new(
+                "E0301",
+                "division by zero".to_string(),
+                operator,
+            ));
+        }
+
+        let result = match operator.token_type {
+            TokenType::Plus => left.checked_add(right),
+            TokenType::Minus => left.checked_sub(right),
+            TokenType::Star => left.checked_mul(right),
+            // like in C, integer division truncates towards zero
+            TokenType::Slash => left.checked_div(right),
+            TokenType::Remainder => left.checked_rem(right),
+            TokenType::Power => {
+                if right < 0 {
+                    return Err(RuntimeError::new(
+                        "E0303",
+                        "negative exponent in integer power".to_string(),
+                        operator,
+                    ));
+                }
+
+                match u32::try_from(right) {
+                    Ok(exp) => left.checked_pow(exp),
+                    // only these bases don't overflow with huge exponents
+                    Err(_) => match left {
+                        0 | 1 => Some(left),
+                        -1 => Some(if right % 2 == 0 { 1 } else { -1 }),
+                        _ => None,
+                    },
+                }
+            }
+            _ => unreachable!(),
+        };
+
+        result
+            .map(Value::Int)
+            .ok_or_else(|| RuntimeError::new("E0302", "integer overflow".to_string(), operator))
+    }
+
+    fn double_arithmetic(left: f64, operator: &Token, right: f64) -> Result<Value, RuntimeError> {
+        let result = match operator.token_type {
+            TokenType::Plus => left + right,
+            TokenType::Minus => left - right,
+            TokenType::Star => left * right,
+            TokenType::Slash => left / right,
+            TokenType::Remainder => left % right,
+            TokenType::Power => left.powf(right),
+            _ => unreachable!(),
+        };
+
+        Ok(Value::Double(result))
+    }
+
+    fn comparison(
+        heap: &Heap,
+        left: Value,
+        operator: &Token,
+        right: Value,
+    ) -> Result<Value, RuntimeError> {
+        let ordering = match (&left, &right) {
+            (Value::Int(l), Value::Int(r)) => l.partial_cmp(r),
+            (Value::Int(l), Value::Double(r)) => (*l as f64).partial_cmp(r),
+            (Value::Double(l), Value::Int(r)) => l.partial_cmp(&(*r as f64)),
+            (Value::Double(l), Value::Double(r)) => l.partial_cmp(r),
+            (Value::String(l), Value::String(r)) => l.partial_cmp(r),
+            (Value::Char(l), Value::Char(r)) => l.partial_cmp(r),
+            _ => {
+                return Err(RuntimeError::new(
+                    "E0207",
+                    format!(
+                        "cannot compare {} with {}",
+                        left.type_name(heap),
+                        right.type_name(heap)
+                    ),
+                    operator,
+                ))
+            }
+        };
+
+        // NaN compares false with everything
+        let result = ordering.is_some_and(|o| match operator.token_type {
+            TokenType::Greater => o.is_gt(),
+            TokenType::Greaterequal => o.is_ge(),
+            TokenType::Less => o.is_lt(),
+            TokenType::Lessequal => o.is_le(),
+            _ => unreachable!(),
+        });
+
+        Ok(Value::Bool(result))
+    }
+
+    fn equals(
+        heap: &Heap,
+        left: &Value,
+        operator: &Token,
+        right: &Value,
+    ) -> Result<bool, RuntimeError> {
+        match (left, right) {
+            (Value::Int(l), Value::Double(r)) => Ok(*l as f64 == *r),
+            (Value::Double(l), Value::Int(r)) => Ok(*l == *r as f64),
+            (Value::Null, _) | (_, Value::Null) => Ok(left == right),
+            (l, r) if l.ty(heap).common(&r.ty(heap)).is_some() => Ok(l == r),
+            (l, r) => Err(RuntimeError::new(
+                "E0207",
+                format!(
+                    "cannot compare {} with {}",
+                    l.type_name(heap),
+                    r.type_name(heap)
+                ),
+                operator,
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+fn run_source(source: &str) -> Result<Value, RuntimeError> {
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    let tokens = Lexer::from_string(source.to_string()).lex_tokens().0;
+    let program = Parser::new(tokens).parse().unwrap();
+
+    Interpreter::new().interpret(&program)
+}
+
+/// a writer whose contents can still be read after it was handed to the interpreter
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuffer(pub(crate) Rc<std::cell::RefCell<Vec<u8>>>);
+
+#[cfg(test)]
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn output_of(source: &str) -> String {
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    let tokens = Lexer::from_string(source.to_string()).lex_tokens().0;
+    let program = Parser::new(tokens).parse().unwrap();
+
+    let buffer = SharedBuffer::default();
+    Interpreter::with_output(Box::new(buffer.clone()))
+        .interpret(&program)
+        .unwrap();
+
+    let output = buffer.0.borrow();
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+#[cfg(test)]
+fn evaluate_source(source: &str) -> Result<Value, RuntimeError> {
+    let tokens = crate::lexer::Lexer::from_string(source.to_string())
+        .lex_tokens()
+        .0;
+    let expr = crate::parser::Parser::new(tokens).expression().unwrap();
+
+    Interpreter::new().evaluate(&expr)
+}
+
+#[test]
+fn test_arithmetic_evaluation() {
+    assert_eq!(evaluate_source("3 + 2 * 4").unwrap(), Value::Int(11));
+    assert_eq!(evaluate_source("7 / 2").unwrap(), Value::Int(3));
+    assert_eq!(evaluate_source("-7 % 3").unwrap(), Value::Int(-1));
+    assert_eq!(evaluate_source("1 + 0.5").unwrap(), Value::Double(1.5));
+    assert_eq!(
+        evaluate_source("\"a\" + 1").unwrap(),
+        Value::String("a1".to_string())
+    );
+}
+
+#[test]
+fn test_comparison_evaluation() {
+    assert_eq!(evaluate_source("5 == (2 + 3)").unwrap(), Value::Bool(true));
+    assert_eq!(evaluate_source("!(1 < 2)").unwrap(), Value::Bool(false));
+    assert!(evaluate_source("\"a\" < 3").is_err());
+}
+
+#[test]
+fn test_char_evaluation() {
+    assert_eq!(evaluate_source("'a' < 'b'").unwrap(), Value::Bool(true));
+    assert_eq!(
+        evaluate_source("\"ab\" + 'c' + '\\n'").unwrap(),
+        Value::String("abc\n".to_string())
+    );
+}
+
+#[test]
+fn test_power_evaluation() {
+    assert_eq!(evaluate_source("2 ** 3 ** 2").unwrap(), Value::Int(512));
+    assert_eq!(evaluate_source("-2 ** 2").unwrap(), Value::Int(-4));
+    assert_eq!(evaluate_source("(1 + 1) ** 10").unwrap(), Value::Int(1024));
+    assert_eq!(evaluate_source("2 ** -1.0").unwrap(), Value::Double(0.5));
+    assert_eq!(evaluate_source("-1 ** 9999999999").unwrap(), Value::Int(-1));
+    assert_eq!(
+        evaluate_source("2 ** 63").unwrap_err().message,
+        "integer overflow"
+    );
+    assert_eq!(
+        evaluate_source("2 ** -1").unwrap_err().message,
+        "negative exponent in integer power"
+    );
+}
+
+#[test]
+fn test_division_by_zero() {
+    let err = evaluate_source("1 / 0").unwrap_err();
+
+    assert_eq!(err.message, "division by zero");
+}
+
+#[test]
+fn test_logical_short_circuit() {
+    let source = "
+        class Counter {
+            int count;
+
+            fn hit() > bool {
+                this.count = this.count + 1;
+                return true;
+            }
+        }
+
+        fn main() > int {
+            Counter c = new Counter();
+            bool a = false && c.hit();
+            bool b = true || c.hit();
+            bool d = true && c.hit();
+            bool e = false || c.hit();
+            return c.count;
+        }
+    ";
+
+    assert_eq!(run_source(source).unwrap(), Value::Int(2));
+    assert_eq!(
+        evaluate_source("1 < 2 && 2 < 3 || false").unwrap(),
+        Value::Bool(true)
+    );
+}
+
+#[test]
+fn test_assignment() {
+    let source = "
+        class Point {
+            double x;
+        }
+
+        fn main() > double {
+            int a = 0;
+            int b = 0;
+            a = b = 2;
+
+            while (a < 100) {
+                a *= 3;
+                a -= 1;
+            }
+
+            Point p = new Point();
+            p.x = 1;
+            p.x += a;
+            p.x **= 2;
+            p.x /= 4;
+            return p.x;
+        }
+    ";
+
+    // a goes 2, 5, 14, 41, 122, p.x is (1 + 122) ** 2 / 4
+    assert_eq!(run_source(source).unwrap(), Value::Double(3782.25));
+}
+
+#[test]
+fn test_function_calls() {
+    let source = "
+        fn fib(int n) > int {
+            if (n < 2) {
+                return n;
+            }
+
+            return fib(n - 1) + fib(n - 2);
+        }
+
+        fn main() > int {
+            int result = fib(10);
+            return result;
+        }
+    ";
+
+    assert_eq!(run_source(source).unwrap(), Value::Int(55));
+}
+
+#[test]
+fn test_block_scoping() {
+    let source = "
+        int a = 1;
+
+        fn main() > int {
+            int b = a;
+            {
+                int a = 10;
+            }
+            return a + b;
+        }
+    ";
+
+    // the inner `a` must not leak out of its block
+    assert_eq!(run_source(source).unwrap(), Value::Int(2));
+}
+
+#[test]
+fn test_classes() {
+    let source = "
+        class Counter {
+            int count = 1;
+            String name;
+
+            Counter(String name) {
+                this.name = name;
+            }
+
+            fn add(int n) > int {
+                this.count = this.count + n;
+                return this.count;
+            }
+        }
+
+        fn main() > String {
+            Counter c = new Counter(\"c\");
+            c.add(2);
+            c.add(3);
+            return c.name + c.count;
+        }
+    ";
+
+    assert_eq!(run_source(source).unwrap(), Value::String("c6".to_string()));
+}
+
+#[test]
+fn test_missing_main() {
+    let err = run_source("fn foo() {}").unwrap_err();
+
+    assert_eq!(err.message, "no 'main' function found");
+}
+
+#[test]
+fn test_out_builtin() {
+    let source = "
+        fn main() {
+            int a = 0;
+
+            while (a < 4) {
+                if (a % 3 == 0) {
+                    out!(a, \" modulo 3 equals 0\");
+                } else {
+                    out!(a);
+                }
+
+                a += 1;
+            }
+
+            out!(1.5, 'c', true, null);
+            out!();
+        }
+    ";
+
+    assert_eq!(
+        output_of(source),
+        "0 modulo 3 equals 0\n1\n2\n3 modulo 3 equals 0\n1.5ctruenull\n\n"
+    );
+}
+
+#[test]
+fn test_arrays() {
+    let source = "
+        fn sum(int[] values) > int {
+            int total = 0;
+            for value in values {
+                total += value;
+            }
+            return total;
+        }
+
+        fn main() {
+            int[] arr = [0, 1, 2, 3];
+            arr[1] = 10;
+            arr[2] *= 5;
+
+            int[][] nested = [[], [1, 2]];
+            nested[0] = arr;
+            nested[0][3] = 4;
+
+            double[] mixed = [1, 2.5];
+            String[] names = [null, \"a\"];
+
+            // literals without an element type take the one of the variable
+            String[] nulls = [null];
+            nulls[0] = \"b\";
+            int[][] empty = [[]];
+            empty[0] = [5];
+            String[][][] deep = [[null], [[]]];
+            deep[1][0] = [\"c\"];
+
+            out!(arr, \" \", sum(arr), \" \", len!(arr), \" \", len!(\"héllo\"));
+            out!(nested, \" \", mixed, \" \", names, \" \", len!([]));
+            out!(nulls, \" \", empty, \" \", deep);
+        }
+    ";
+
+    assert_eq!(
+        output_of(source),
+        "[0, 10, 10, 4] 24 4 5\n[[0, 10, 10, 4], [1, 2]] [1.0, 2.5] [null, a] 0\n[b] [[5]] [[null], [[c]]]\n"
+    );
+}
+
+#[test]
+fn test_index_out_of_bounds() {
+    let source = "
+        fn main() {
+            int[] arr = [1, 2];
+            arr[1 + 1] = 3;
+        }
+    ";
+
+    let err = run_source(source).unwrap_err();
+    assert_eq!(err.message, "index 2 out of bounds for length 2");
+    // the error points at the index, not the whole expression
+    assert_eq!(&source[err.span.start..err.span.end], "1 + 1");
+
+    let err = evaluate_source("[1, 2, 3][-1]").unwrap_err();
+    assert_eq!(err.message, "index -1 out of bounds for length 3");
+}
+
+#[test]
+fn test_garbage_collection() {
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    let source = "
+        class Node {
+            Node next;
+            int[] values = [1, 2];
+        }
+
+        fn cycle() > Node {
+            Node a = new Node();
+            a.next = new Node();
+            a.next.next = a;
+            return a;
+        }
+
+        fn main() {
+            Node kept = cycle();
+            for (int i = 0; i < 100; i += 1) {
+                cycle();
+            }
+
+            // temporaries have to survive collections while the expression is evaluated
+            int[][] nested = [[len!([1, 2, 3])], [kept.values[0] + cycle().values[1]]];
+            out!(nested, \" \", kept.next.next == kept, \" \", [new Node(), new Node()][1].values);
+        }
+    ";
+
+    let tokens = Lexer::from_string(source.to_string()).lex_tokens().0;
+    let program = Parser::new(tokens).parse().unwrap();
+
+    let buffer = SharedBuffer::default();
+    let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+    // collect on every allocation, so a missing root frees a live object
+    interpreter.heap = Heap::stress();
+    interpreter.interpret(&program).unwrap();
+
+    let output = String::from_utf8_lossy(&buffer.0.borrow()).into_owned();
+    assert_eq!(output, "[[3], [3]] true [1, 2]\n");
+
+    // every call of `cycle` leaves two nodes with an array each behind
+    let stats = interpreter.gc_stats();
+    assert!(stats.collections > 400);
+    assert!(stats.objects_freed >= 400);
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use super::value::Value;
+use crate::parser::ast::{ClassDecl, Type};
+
+/// bytes which can be allocated before the first collection
+const INITIAL_THRESHOLD: usize = 1024 * 1024;
+
+/// after a collection the next one happens once the live bytes have grown by this factor
+const GROWTH_FACTOR: usize = 2;
+
+/// a reference to an object on the heap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Handle(usize);
+
+#[derive(Debug)]
+pub struct Instance {
+    pub class: Rc<ClassDecl>,
+    pub fields: HashMap<String, Value>,
+}
+
+#[derive(Debug)]
+pub struct Array {
+    pub ty: Type, // type of the elements
+    pub elements: Vec<Value>,
+}
+
+#[derive(Debug)]
+pub enum Object {
+    Instance(Instance),
+    Array(Array),
+}
+
+impl Object {
+    /// estimated number of bytes used by the object
+    fn size(&self) -> usize {
+        let values = match self {
+            Object::Instance(instance) => instance.class.fields.len(),
+            Object::Array(array) => array.elements.len(),
+        };
+
+        mem::size_of::<Slot>() + values * mem::size_of::<Value>()
+    }
+
+    fn references(&self) -> Box<dyn Iterator<Item = Handle> + '_> {
+        match self {
+            Object::Instance(instance) => {
+                Box::new(instance.fields.values().filter_map(Value::handle))
+            }
+            Object::Array(array) => Box::new(array.elements.iter().filter_map(Value::handle)),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Slot {
+    marked: bool,
+    size: usize,
+    object: Object,
+}
+
+#[derive(Debug, Default)]
+pub struct GcStats {
+    pub collections: usize,
+    pub objects_freed: usize,
+    pub bytes_freed: usize,
+    pub bytes_live: usize,
+    pub total_pause: Duration,
+    pub max_pause: Duration,
+}
+
+impl fmt::Display for GcStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "gc collections: {}", self.collections)?;
+        writeln!(
+            f,
+            "gc freed:       {} bytes in {} objects",
+            self.bytes_freed, self.objects_freed
+        )?;
+        writeln!(f, "gc live:        {} bytes", self.bytes_live)?;
+        writeln!(f, "gc total pause: {:?}", self.total_pause)?;
+        write!(f, "gc max pause:   {:?}", self.max_pause)
+    }
+}
+
+/// mark and sweep collected storage for objects and arrays,
+/// the roots have to be passed in by the interpreter
+#[derive(Debug)]
+pub struct Heap {
+    slots: Vec<Option<Slot>>,
+    free: Vec<usize>,
+    bytes_allocated: usize,
+    next_gc: usize,
+    // collect on every allocation to find objects which are missing from the roots
+    stress: bool,
+    stats: GcStats,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Heap::with_threshold(INITIAL_THRESHOLD)
+    }
+}
+
+impl Heap {
+    pub fn new() -> Self {
+        Heap::default()
+    }
+
+    /// a heap which collects as soon as more than `threshold` bytes are allocated
+    fn with_threshold(threshold: usize) -> Self {
+        Heap {
+            slots: Vec::new(),
+            free: Vec::new(),
+            bytes_allocated: 0,
+            next_gc: threshold,
+            stress: false,
+            stats: GcStats::default(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn stress() -> Self {
+        Heap {
+            stress: true,
+            ..Heap::default()
+        }
+    }
+
+    pub fn allocate(&mut self, object: Object) -> Handle {
+        let size = object.size();
+        self.bytes_allocated += size;
+
+        let slot = Some(Slot {
+            marked: false,
+            size,
+            object,
+        });
+
+        match self.free.pop() {
+            Some(index) => {
+                self.slots[index] = slot;
+                Handle(index)
+            }
+            None => {
+                self.slots.push(slot);
+                Handle(self.slots.len() - 1)
+            }
+        }
+    }
+
+    pub fn should_collect(&self) -> bool {
+        self.stress || self.bytes_allocated > self.next_gc
+    }
+
+    /// frees every object which can't be reached from the roots
+    pub fn collect(&mut self, roots: impl Iterator<Item = Handle>) {
+        let start = Instant::now();
+
+        self.mark(roots.collect());
+        self.sweep();
+
+        self.next_gc = self.next_gc.max(self.bytes_allocated * GROWTH_FACTOR);
+
+        let pause = start.elapsed();
+        self.stats.collections += 1;
+        self.stats.total_pause += pause;
+        self.stats.max_pause = self.stats.max_pause.max(pause);
+    }
+
+    fn mark(&mut self, mut gray: Vec<Handle>) {
+        while let Some(handle) = gray.pop() {
+            let slot = self.slots[handle.0]
+                .as_mut()
+                .expect("handle to a freed object");
+
+            if !slot.marked {
+                slot.marked = true;
+                gray.extend(slot.object.references());
+            }
+        }
+    }
+
+    fn sweep(&mut self) {
+        for (index, entry) in self.slots.iter_mut().enumerate() {
+            match entry {
+                Some(slot) if slot.marked => slot.marked = false,
+                Some(slot) => {
+                    self.bytes_allocated -= slot.size;
+                    self.stats.bytes_freed += slot.size;
+                    self.stats.objects_freed += 1;
+
+                    *entry = None;
+                    self.free.push(index);
+                }
+                None => {}
+            }
+        }
+    }
+
+    pub fn stats(&mut self) -> &GcStats {
+        self.stats.bytes_live = self.bytes_allocated;
+        &self.stats
+    }
+
+    fn get(&self, handle: Handle) -> &Object {
+        match &self.slots[handle.0] {
+            Some(slot) => &slot.object,
+            None => panic!("handle to a freed object"),
+        }
+    }
+
+    fn get_mut(&mut self, handle: Handle) -> &mut Object {
+        match &mut self.slots[handle.0] {
+            Some(slot) => &mut slot.object,
+            None => panic!("handle to a freed object"),
+        }
+    }
+
+    pub fn instance(&self, handle: Handle) -> &Instance {
+        match self.get(handle) {
+            Object::Instance(instance) => instance,
+            Object::Array(_) => panic!("handle to an array used as an instance"),
+        }
+    }
+
+    pub fn instance_mut(&mut self, handle: Handle) -> &mut Instance {
+        match self.get_mut(handle) {
+            Object::Instance(instance) => instance,
+            Object::Array(_) => panic!("handle to an array used as an instance"),
+        }
+    }
+
+    pub fn array(&self, handle: Handle) -> &Array {
+        match self.get(handle) {
+            Object::Array(array) => array,
+            Object::Instance(_) => panic!("handle to an instance used as an array"),
+        }
+    }
+
+    pub fn array_mut(&mut self, handle: Handle) -> &mut Array {
+        match self.get_mut(handle) {
+            Object::Array(array) => array,
+            Object::Instance(_) => panic!("handle to an instance used as an array"),
+        }
+    }
+}
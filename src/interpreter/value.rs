@@ -0,0 +1,125 @@
+use std::fmt;
+
+use super::heap::{Handle, Heap};
+use crate::parser::ast::Type;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Double(f64),
+    Bool(bool),
+    Char(char),
+    String(String),
+    // objects and arrays are compared by identity
+    Object(Handle),
+    Array(Handle),
+    Null,
+}
+
+impl Value {
+    /// the heap object the value refers to
+    pub fn handle(&self) -> Option<Handle> {
+        match self {
+            Value::Object(handle) | Value::Array(handle) => Some(*handle),
+            _ => None,
+        }
+    }
+
+    pub fn ty(&self, heap: &Heap) -> Type {
+        match self {
+            Value::Int(_) => Type::Int,
+            Value::Double(_) => Type::Double,
+            Value::Bool(_) => Type::Bool,
+            Value::Char(_) => Type::Char,
+            Value::String(_) => Type::String,
+            Value::Object(handle) => Type::Class(heap.instance(*handle).class.name.lexeme.clone()),
+            Value::Array(handle) => Type::Array(Box::new(heap.array(*handle).ty.clone())),
+            Value::Null => Type::Null,
+        }
+    }
+
+    pub fn type_name(&self, heap: &Heap) -> String {
+        self.ty(heap).to_string()
+    }
+
+    /// converts the value so it can be stored in a slot of the given type,
+    /// ints are widened to doubles like in C
+    pub fn coerce(self, ty: &Type, heap: &mut Heap) -> Option<Value> {
+        match (ty, self) {
+            (Type::Int, Value::Int(i)) => Some(Value::Int(i)),
+            (Type::Double, Value::Double(d)) => Some(Value::Double(d)),
+            (Type::Double, Value::Int(i)) => Some(Value::Double(i as f64)),
+            (Type::Bool, Value::Bool(b)) => Some(Value::Bool(b)),
+            (Type::Char, Value::Char(c)) => Some(Value::Char(c)),
+            (Type::String, Value::String(s)) => Some(Value::String(s)),
+            (Type::Class(name), Value::Object(handle)) => {
+                if heap.instance(handle).class.name.lexeme == *name {
+                    Some(Value::Object(handle))
+                } else {
+                    None
+                }
+            }
+            (Type::Array(element), Value::Array(handle)) => {
+                let array = heap.array_mut(handle);
+
+                if array.ty == **element {
+                    Some(Value::Array(handle))
+                } else if element.fits_elements(&array.ty) {
+                    // a literal which is empty or only holds `null` or such arrays
+                    // gets the type of the first slot it is stored in
+                    array.ty = (**element).clone();
+                    for i in 0..heap.array(handle).elements.len() {
+                        let value = heap.array(handle).elements[i].clone();
+                        heap.array_mut(handle).elements[i] = value.coerce(element, heap)?;
+                    }
+                    Some(Value::Array(handle))
+                } else {
+                    None
+                }
+            }
+            (
+                Type::String | Type::Class(_) | Type::Array(_) | Type::Null | Type::Void,
+                Value::Null,
+            ) => Some(Value::Null),
+            _ => None,
+        }
+    }
+
+    /// the value can only be printed together with the heap it lives in
+    pub fn display<'a>(&'a self, heap: &'a Heap) -> Display<'a> {
+        Display { value: self, heap }
+    }
+}
+
+pub struct Display<'a> {
+    value: &'a Value,
+    heap: &'a Heap,
+}
+
+impl fmt::Display for Display<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            Value::Int(i) => write!(f, "{}", i),
+            // debug formatting keeps the fractional part, so 13.0 stays 13.0
+            Value::Double(d) => write!(f, "{:?}", d),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Char(c) => write!(f, "{}", c),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Object(handle) => {
+                let instance = self.heap.instance(*handle);
+                write!(f, "{} instance", instance.class.name.lexeme)
+            }
+            Value::Array(handle) => {
+                write!(f, "[")?;
+                for (i, element) in self.heap.array(*handle).elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element.display(self.heap))?;
+                }
+                write!(f, "]")
+            }
+            Value::Null => write!(f, "null"),
+        }
+    }
+}
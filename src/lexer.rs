@@ -1,39 +1,34 @@
 pub mod token;
+pub mod trivia;
 
 use self::token::{TokenSpan, TokenType};
-use crate::reporter;
+use self::trivia::{SyntaxToken, SyntaxTokens, Trivia, TriviaKind};
+use crate::reporter::diagnostic::Diagnostic;
 use token::Token;
 
 #[derive(Debug)]
 pub struct Lexer {
-    file_path: String,
     source: Vec<char>,
     start: usize,
     current: usize,
-    error: bool,
+    diagnostics: Vec<Diagnostic>,
+    // value of the last char literal, it is unescaped once to check its length
+    char_value: Option<String>,
 }
 
 impl Lexer {
-    pub fn from_file(path: &str) -> Self {
-        let source = std::fs::read_to_string(path).unwrap();
-
-        let mut ret = Lexer::from_string(source);
-        ret.file_path = String::from(path);
-
-        ret
-    }
-
     pub fn from_string(source: String) -> Self {
         Lexer {
-            file_path: String::new(),
             source: source.chars().collect(),
             start: 0,
             current: 0,
-            error: false,
+            diagnostics: Vec::new(),
+            char_value: None,
         }
     }
 
-    pub fn lex_tokens(&mut self) -> Option<Vec<Token>> {
+    /// lexes the whole source, the tokens around lexical errors are still returned
+    pub fn lex_tokens(&mut self) -> (Vec<Token>, Vec<Diagnostic>) {
         let mut tokens: Vec<Token> = Vec::new();
 
         while !self.is_at_end() {
@@ -42,24 +37,90 @@ impl Lexer {
 
             let token = self.get_next_token();
 
-            if let Some(mut t) = token {
-                if t.token_type == TokenType::String {
-                    // remove the quotes from the string
-                    // this does not panic, because a String token is only created
-                    // if it has two quotes
-                    t.lexeme.remove(0);
-                    t.lexeme.remove(t.lexeme.len() - 1);
-                }
-
+            if let Some(t) = token {
                 tokens.push(t);
             }
         }
 
-        if self.error {
-            return None;
+        (tokens, std::mem::take(&mut self.diagnostics))
+    }
+
+    /// lexes the whole source without losing anything, whitespace, comments
+    /// and the text of lexical errors are attached to the tokens as trivia
+    pub fn lex_lossless(&mut self) -> (SyntaxTokens, Vec<Diagnostic>) {
+        let mut tokens: Vec<SyntaxToken> = Vec::new();
+        let mut leading: Vec<Trivia> = Vec::new();
+        // trivia belongs to the previous token until the line ends
+        let mut trailing = false;
+
+        while !self.is_at_end() {
+            self.start = self.current;
+
+            if let Some(token) = self.get_next_token() {
+                tokens.push(SyntaxToken {
+                    token,
+                    text: self.get_lexem_string(),
+                    leading: std::mem::take(&mut leading),
+                    trailing: Vec::new(),
+                });
+                trailing = true;
+                continue;
+            }
+
+            let trivia = self.trivia();
+            let is_newline = trivia.kind == TriviaKind::Newline;
+
+            match tokens.last_mut() {
+                Some(token) if trailing => Lexer::push_trivia(&mut token.trailing, trivia),
+                _ => Lexer::push_trivia(&mut leading, trivia),
+            }
+
+            if is_newline {
+                trailing = false;
+            }
+        }
+
+        let tokens = SyntaxTokens {
+            tokens,
+            end: leading,
+        };
+
+        (tokens, std::mem::take(&mut self.diagnostics))
+    }
+
+    /// the text consumed without producing a token
+    fn trivia(&self) -> Trivia {
+        let text = self.get_lexem_string();
+
+        let kind = match self.source[self.start] {
+            ' ' | '\t' | '\r' => TriviaKind::Whitespace,
+            '\n' => TriviaKind::Newline,
+            _ if text.starts_with("//") => TriviaKind::LineComment,
+            _ if text.starts_with("/*") => TriviaKind::BlockComment,
+            _ => TriviaKind::Skipped,
+        };
+
+        Trivia {
+            kind,
+            text,
+            span: TokenSpan {
+                start: self.start,
+                end: self.current,
+            },
         }
+    }
 
-        Some(tokens)
+    /// whitespace is lexed one char at a time, so runs of it are merged
+    fn push_trivia(trivia: &mut Vec<Trivia>, next: Trivia) {
+        match trivia.last_mut() {
+            Some(last)
+                if last.kind == TriviaKind::Whitespace && next.kind == TriviaKind::Whitespace =>
+            {
+                last.text.push_str(&next.text);
+                last.span.end = next.span.end;
+            }
+            _ => trivia.push(next),
+        }
     }
 
     fn get_next_token(&mut self) -> Option<Token> {
@@ -74,21 +135,20 @@ impl Lexer {
             ']' => Some(TokenType::Rightbrack),
             ',' => Some(TokenType::Comma),
             '.' => Some(TokenType::Dot),
-            '+' => Some(TokenType::Plus),
-            '-' => Some(TokenType::Minus),
             ';' => Some(TokenType::Semicolon),
-            '%' => Some(TokenType::Remainder),
             '\r' => None,
             '\t' => None,
             ' ' => None,
             '\n' => None,
             '"' => self.string_token(),
+            '\'' => self.char_token(),
 
             // TODO: use macro to create two character tokens
             '&' => {
                 if self.match_next('&') {
                     Some(TokenType::And)
                 } else {
+                    self.unexpected_character(c, "did you mean '&&'?");
                     None
                 }
             }
@@ -96,12 +156,40 @@ impl Lexer {
                 if self.match_next('|') {
                     Some(TokenType::Or)
                 } else {
+                    self.unexpected_character(c, "did you mean '||'?");
                     None
                 }
             }
+            '+' => {
+                if self.match_next('=') {
+                    Some(TokenType::Plusequal)
+                } else {
+                    Some(TokenType::Plus)
+                }
+            }
+            '-' => {
+                if self.match_next('=') {
+                    Some(TokenType::Minusequal)
+                } else {
+                    Some(TokenType::Minus)
+                }
+            }
+            '%' => {
+                if self.match_next('=') {
+                    Some(TokenType::Remainderequal)
+                } else {
+                    Some(TokenType::Remainder)
+                }
+            }
             '*' => {
                 if self.match_next('*') {
-                    Some(TokenType::Power)
+                    if self.match_next('=') {
+                        Some(TokenType::Powerequal)
+                    } else {
+                        Some(TokenType::Power)
+                    }
+                } else if self.match_next('=') {
+                    Some(TokenType::Starequal)
                 } else {
                     Some(TokenType::Star)
                 }
@@ -136,11 +224,27 @@ impl Lexer {
             }
             '/' => {
                 if self.match_next('/') {
-                    while self.peek() != '\n' && !self.is_at_end() {
+                    // `///` starts a doc comment, but `////` is a normal comment again
+                    let is_doc = self.peek() == '/' && self.peek_next() != '/';
+
+                    // the '\r' of a "\r\n" line break isn't part of the comment
+                    while !self.is_at_end()
+                        && self.peek() != '\n'
+                        && !(self.peek() == '\r' && self.peek_next() == '\n')
+                    {
                         self.advance();
                     }
 
+                    if is_doc {
+                        Some(TokenType::DocComment)
+                    } else {
+                        None
+                    }
+                } else if self.match_next('*') {
+                    self.block_comment();
                     None
+                } else if self.match_next('=') {
+                    Some(TokenType::Slashequal)
                 } else {
                     Some(TokenType::Slash)
                 }
@@ -150,35 +254,75 @@ impl Lexer {
                 if Lexer::is_alpha(c) {
                     self.identifier_token()
                 } else {
-                    reporter::report_error(
-                        &format!("unexpected character '{}' found", c),
-                        &self.file_path,
-                        TokenSpan {
-                            start: self.current,
-                            end: self.current + 1,
-                        },
-                        None,
-                    );
-                    self.error = true;
+                    self.unexpected_character(c, "unexpected character");
                     None
                 }
             }
         };
 
         if let Some(t) = token {
+            let lexeme = match t {
+                // the lexeme of a literal is its value without quotes
+                TokenType::String => self.unescape(self.start + 1, self.current - 1),
+                TokenType::Char => self.char_value.take().unwrap_or_default(),
+                TokenType::DocComment => Lexer::doc_comment_text(&self.get_lexem_string()),
+                TokenType::Int | TokenType::Double => {
+                    Lexer::number_lexeme(&self.get_lexem_string())
+                }
+                _ => self.get_lexem_string(),
+            };
+
             Some(Token {
                 token_type: t,
                 span: TokenSpan {
                     start: self.start,
                     end: self.current,
                 },
-                lexeme: self.get_lexem_string(),
+                lexeme,
             })
         } else {
             None
         }
     }
 
+    /// skips a block comment, the opening `/*` is already consumed
+    fn block_comment(&mut self) {
+        let mut depth = 1;
+
+        while depth > 0 && !self.is_at_end() {
+            if self.peek() == '/' && self.peek_next() == '*' {
+                self.current += 2;
+                depth += 1;
+            } else if self.peek() == '*' && self.peek_next() == '/' {
+                self.current += 2;
+                depth -= 1;
+            } else {
+                self.advance();
+            }
+        }
+
+        if depth > 0 {
+            self.error(
+                "E0008",
+                "unterminated block comment".to_string(),
+                TokenSpan {
+                    start: self.start,
+                    end: self.start + 2,
+                },
+                "this comment is never closed",
+            );
+        }
+    }
+
+    fn doc_comment_text(lexeme: &str) -> String {
+        let text = lexeme.trim_start_matches("///");
+
+        text.strip_prefix(' ')
+            .unwrap_or(text)
+            .trim_end()
+            .to_string()
+    }
+
     fn identifier_token(&mut self) -> Option<TokenType> {
         while self.peek().is_ascii_digit() || Lexer::is_alpha(self.peek()) {
             self.advance();
@@ -191,6 +335,7 @@ impl Lexer {
             "fn" => Some(TokenType::Fn),
             "for" => Some(TokenType::For),
             "if" => Some(TokenType::If),
+            "new" => Some(TokenType::New),
             "null" => Some(TokenType::Null),
             "return" => Some(TokenType::Return),
             "super" => Some(TokenType::Super),
@@ -203,47 +348,185 @@ impl Lexer {
     }
 
     fn number_token(&mut self) -> Option<TokenType> {
+        if self.source[self.start] == '0' {
+            let radix = match self.peek() {
+                'x' => Some(16),
+                'o' => Some(8),
+                'b' => Some(2),
+                _ => None,
+            };
+
+            if let Some(radix) = radix {
+                self.advance();
+                return self.radix_number_token(radix);
+            }
+        }
+
         let mut is_float = false;
 
-        while self.peek().is_ascii_digit() {
-            self.advance();
-        }
+        self.digits();
 
         if self.peek() == '.' && self.peek_next().is_ascii_digit() {
             is_float = true;
 
             self.advance();
 
-            while self.peek().is_ascii_digit() {
+            self.digits();
+        }
+
+        // only an `e` followed by digits is an exponent, `1e` stays an int and an identifier
+        let sign = matches!(self.peek_next(), '+' | '-');
+        let exponent_digit = if sign {
+            self.source.get(self.current + 2).copied().unwrap_or('\0')
+        } else {
+            self.peek_next()
+        };
+
+        if matches!(self.peek(), 'e' | 'E') && exponent_digit.is_ascii_digit() {
+            is_float = true;
+
+            self.advance();
+            if sign {
                 self.advance();
             }
+
+            self.digits();
         }
 
+        let text = Lexer::number_lexeme(&self.get_lexem_string());
+
         if is_float {
+            if text.parse::<f64>().map_or(true, |d| d.is_infinite()) {
+                self.number_error("E0012", "float literal out of range for double (f64)");
+                return None;
+            }
+
             Some(TokenType::Double)
         } else {
+            if text.parse::<i64>().is_err() {
+                self.number_error("E0009", "integer literal out of range for int (i64)");
+                return None;
+            }
+
             Some(TokenType::Int)
         }
     }
 
+    /// lexes the digits after a `0x`, `0o` or `0b` prefix
+    fn radix_number_token(&mut self, radix: u32) -> Option<TokenType> {
+        let name = match radix {
+            16 => "hexadecimal",
+            8 => "octal",
+            _ => "binary",
+        };
+
+        // take every alphanumeric character, so `0b102` reports the 2
+        let digits_start = self.current;
+        while self.peek().is_ascii_alphanumeric() || self.peek() == '_' {
+            self.advance();
+        }
+
+        let mut valid = true;
+        let mut has_digits = false;
+
+        for i in digits_start..self.current {
+            let c = self.source[i];
+
+            if c == '_' {
+                continue;
+            }
+
+            has_digits = true;
+
+            if !c.is_digit(radix) {
+                self.error(
+                    "E0010",
+                    format!("invalid digit '{}' in {} literal", c, name),
+                    TokenSpan {
+                        start: i,
+                        end: i + 1,
+                    },
+                    "invalid digit",
+                );
+                valid = false;
+            }
+        }
+
+        if !has_digits {
+            self.number_error("E0011", "missing digits after the integer prefix");
+            return None;
+        }
+
+        if !valid {
+            return None;
+        }
+
+        let digits: String = self.source[digits_start..self.current]
+            .iter()
+            .filter(|c| **c != '_')
+            .collect();
+
+        if i64::from_str_radix(&digits, radix).is_err() {
+            self.number_error("E0009", "integer literal out of range for int (i64)");
+            return None;
+        }
+
+        Some(TokenType::Int)
+    }
+
+    /// decimal digits with `_` as separator
+    fn digits(&mut self) {
+        while self.peek().is_ascii_digit() || self.peek() == '_' {
+            self.advance();
+        }
+    }
+
+    /// turns a valid number literal into a string which can be parsed by rust,
+    /// separators are removed and prefixed integers are converted to decimal
+    fn number_lexeme(raw: &str) -> String {
+        let text: String = raw.chars().filter(|c| *c != '_').collect();
+
+        let radix = match text.get(..2) {
+            Some("0x") => 16,
+            Some("0o") => 8,
+            Some("0b") => 2,
+            _ => return text,
+        };
+
+        i64::from_str_radix(&text[2..], radix).map_or(text, |value| value.to_string())
+    }
+
+    fn number_error(&mut self, code: &'static str, message: &str) {
+        self.error(
+            code,
+            message.to_string(),
+            TokenSpan {
+                start: self.start,
+                end: self.current,
+            },
+            "this literal",
+        );
+    }
+
     fn string_token(&mut self) -> Option<TokenType> {
         while self.peek() != '"' && !self.is_at_end() {
-            self.advance();
+            // an escaped quote does not end the string
+            if self.advance() == '\\' && !self.is_at_end() {
+                self.advance();
+            }
         }
 
         if self.is_at_end() {
-            reporter::report_error(
-                "underminated string",
-                &self.file_path,
+            self.error(
+                "E0002",
+                "underminated string".to_string(),
                 TokenSpan {
                     start: self.start,
                     end: self.start + 1,
                 },
-                Some("this string is unterminated"),
+                "this string is unterminated",
             );
 
-            self.error = true;
-
             return None;
         }
 
@@ -253,6 +536,177 @@ impl Lexer {
         Some(TokenType::String)
     }
 
+    fn char_token(&mut self) -> Option<TokenType> {
+        while self.peek() != '\'' && self.peek() != '\n' && !self.is_at_end() {
+            if self.advance() == '\\' && !self.is_at_end() {
+                self.advance();
+            }
+        }
+
+        if self.peek() != '\'' {
+            self.error(
+                "E0005",
+                "unterminated character literal".to_string(),
+                TokenSpan {
+                    start: self.start,
+                    end: self.current,
+                },
+                "missing closing '",
+            );
+
+            return None;
+        }
+
+        // closing '
+        self.advance();
+
+        let value = self.unescape(self.start + 1, self.current - 1);
+        let span = TokenSpan {
+            start: self.start,
+            end: self.current,
+        };
+
+        match value.chars().count() {
+            1 => {
+                self.char_value = Some(value);
+                Some(TokenType::Char)
+            }
+            0 => {
+                self.error(
+                    "E0006",
+                    "empty character literal".to_string(),
+                    span,
+                    "expected a character",
+                );
+                None
+            }
+            _ => {
+                self.error(
+                    "E0007",
+                    "character literal may only contain one character".to_string(),
+                    span,
+                    "use double quotes for a String",
+                );
+                None
+            }
+        }
+    }
+
+    /// resolves the escape sequences in source[start..end]
+    fn unescape(&mut self, start: usize, end: usize) -> String {
+        let mut value = String::new();
+        let mut i = start;
+
+        while i < end {
+            let c = self.source[i];
+            i += 1;
+
+            if c != '\\' {
+                value.push(c);
+                continue;
+            }
+
+            let escape_start = i - 1;
+            let escaped = if i < end { self.source[i] } else { '\0' };
+            i += 1;
+
+            let resolved = match escaped {
+                'n' => Some('\n'),
+                't' => Some('\t'),
+                'r' => Some('\r'),
+                '0' => Some('\0'),
+                '\\' => Some('\\'),
+                '"' => Some('"'),
+                '\'' => Some('\''),
+                'u' => {
+                    let (resolved, next) = self.unicode_escape(i, end);
+                    i = next;
+
+                    if resolved.is_none() {
+                        self.error(
+                            "E0004",
+                            "invalid unicode escape".to_string(),
+                            TokenSpan {
+                                start: escape_start,
+                                end: i,
+                            },
+                            "expected \\u{...} with 1 to 6 hex digits of a valid character",
+                        );
+                    }
+
+                    resolved
+                }
+                _ => {
+                    self.error(
+                        "E0003",
+                        format!("unknown escape sequence '\\{}'", escaped),
+                        TokenSpan {
+                            start: escape_start,
+                            end: i.min(end),
+                        },
+                        "unknown escape",
+                    );
+                    None
+                }
+            };
+
+            // keep going with a placeholder so later errors are still found
+            value.push(resolved.unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+
+        value
+    }
+
+    /// parses the `{...}` part of a \u escape starting at source[i],
+    /// returns the character and the index after the escape
+    fn unicode_escape(&self, mut i: usize, end: usize) -> (Option<char>, usize) {
+        if i >= end || self.source[i] != '{' {
+            return (None, i);
+        }
+        i += 1;
+
+        let digits_start = i;
+        while i < end && self.source[i] != '}' {
+            i += 1;
+        }
+
+        if i >= end {
+            return (None, i);
+        }
+
+        let digits: String = self.source[digits_start..i].iter().collect();
+        // closing }
+        i += 1;
+
+        // from_str_radix would also take a sign like `+41`
+        if digits.is_empty() || digits.len() > 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return (None, i);
+        }
+
+        let c = u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32);
+
+        (c, i)
+    }
+
+    fn unexpected_character(&mut self, c: char, label: &str) {
+        self.error(
+            "E0001",
+            format!("unexpected character '{}' found", c),
+            TokenSpan {
+                start: self.start,
+                end: self.current,
+            },
+            label,
+        );
+    }
+
+    fn error(&mut self, code: &'static str, message: String, span: TokenSpan, label: &str) {
+        self.diagnostics
+            .push(Diagnostic::error(code, message, span).with_label(label));
+    }
+
     fn get_lexem_string(&self) -> String {
         self.source[self.start..self.current].iter().collect()
     }
@@ -307,8 +761,9 @@ impl Lexer {
 fn test_string_lexing() {
     let mut l = Lexer::from_string(String::from("\"test\""));
 
-    let tokens = l.lex_tokens().unwrap();
+    let (tokens, diagnostics) = l.lex_tokens();
 
+    assert!(diagnostics.is_empty());
     assert_eq!(tokens[0].lexeme, "test");
 }
 
@@ -316,9 +771,12 @@ fn test_string_lexing() {
 fn test_underminated_string_lexing() {
     let mut l = Lexer::from_string(String::from("\"underminated"));
 
-    let tokens = l.lex_tokens();
+    let (tokens, diagnostics) = l.lex_tokens();
 
-    assert!(tokens.is_none());
+    assert!(tokens.is_empty());
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, "E0002");
+    assert_eq!(diagnostics[0].message, "underminated string");
 }
 
 #[test]
@@ -331,7 +789,214 @@ fn test_line_number_lexing() {
 
     let mut l = Lexer::from_string(String::from(source_string));
 
-    let mut tokens = l.lex_tokens().unwrap();
+    let (mut tokens, _) = l.lex_tokens();
 
     assert_eq!(tokens.pop().unwrap().token_type, TokenType::Rightcurl);
 }
+
+#[test]
+fn test_multiple_lexical_errors() {
+    let mut l = Lexer::from_string(String::from("int a = 1 # 2 @;"));
+
+    let (tokens, diagnostics) = l.lex_tokens();
+
+    assert_eq!(tokens.len(), 6);
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].message, "unexpected character '#' found");
+    assert_eq!(diagnostics[0].span.start, 10);
+    assert_eq!(diagnostics[1].message, "unexpected character '@' found");
+    assert!(diagnostics.iter().all(|d| d.is_error()));
+}
+
+#[test]
+fn test_escape_sequences() {
+    let mut l = Lexer::from_string(String::from(r#""a\n\t\\\"b" '\'' 'x' '\u{1F600}'"#));
+
+    let (tokens, diagnostics) = l.lex_tokens();
+
+    assert!(diagnostics.is_empty());
+    assert_eq!(tokens[0].lexeme, "a\n\t\\\"b");
+    assert_eq!(tokens[1].token_type, TokenType::Char);
+    assert_eq!(tokens[1].lexeme, "'");
+    assert_eq!(tokens[2].lexeme, "x");
+    assert_eq!(tokens[3].lexeme, "\u{1F600}");
+}
+
+#[test]
+fn test_invalid_escape_sequences() {
+    let mut l = Lexer::from_string(String::from(
+        r#""\q" "\u{110000}" '' 'ab' "\u{+41}" '\u{-1}'"#,
+    ));
+
+    let (_, diagnostics) = l.lex_tokens();
+    let codes: Vec<&str> = diagnostics.iter().map(|d| d.code).collect();
+
+    assert_eq!(
+        codes,
+        vec!["E0003", "E0004", "E0006", "E0007", "E0004", "E0004"]
+    );
+    assert_eq!(diagnostics[0].message, "unknown escape sequence '\\q'");
+}
+
+#[test]
+fn test_invalid_char_escape_reported_once() {
+    let mut l = Lexer::from_string(String::from(r"char c = '\q';"));
+
+    let (tokens, diagnostics) = l.lex_tokens();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, "E0003");
+    assert_eq!(tokens[3].token_type, TokenType::Char);
+}
+
+#[test]
+fn test_comments() {
+    let source_string = "\
+        /// adds two numbers
+        ///
+        /// more text
+        //// not a doc comment
+        /* outer /* nested */ still a comment */
+        fn /**/ add() {}
+    ";
+
+    let mut l = Lexer::from_string(String::from(source_string));
+
+    let (tokens, diagnostics) = l.lex_tokens();
+    let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+
+    assert!(diagnostics.is_empty());
+    assert_eq!(tokens[0].lexeme, "adds two numbers");
+    assert_eq!(tokens[1].lexeme, "");
+    assert_eq!(tokens[2].lexeme, "more text");
+    assert_eq!(
+        types[3..],
+        [
+            TokenType::Fn,
+            TokenType::Identifier,
+            TokenType::Leftparen,
+            TokenType::Rightparen,
+            TokenType::Leftcurl,
+            TokenType::Rightcurl
+        ]
+    );
+}
+
+#[test]
+fn test_unterminated_block_comment() {
+    let mut l = Lexer::from_string(String::from("int a; /* /* */"));
+
+    let (tokens, diagnostics) = l.lex_tokens();
+
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].message, "unterminated block comment");
+    assert_eq!(diagnostics[0].span.start, 7);
+}
+
+#[test]
+fn test_number_literals() {
+    let mut l = Lexer::from_string(String::from(
+        "0xFF 0o17 0b1010_1010 1_000_000 1.5e-3 2E10 1_0.2_5 1e",
+    ));
+
+    let (tokens, diagnostics) = l.lex_tokens();
+    let lexemes: Vec<&str> = tokens.iter().map(|t| t.lexeme.as_str()).collect();
+
+    assert!(diagnostics.is_empty());
+    assert_eq!(
+        lexemes,
+        vec!["255", "15", "170", "1000000", "1.5e-3", "2E10", "10.25", "1", "e"]
+    );
+    assert_eq!(tokens[4].token_type, TokenType::Double);
+    assert_eq!(tokens[5].token_type, TokenType::Double);
+    assert_eq!(tokens[7].token_type, TokenType::Int);
+}
+
+#[test]
+fn test_invalid_number_literals() {
+    let mut l = Lexer::from_string(String::from(
+        "99999999999999999999 0b102 0x 0xFFFFFFFFFFFFFFFF 1e400",
+    ));
+
+    let (tokens, diagnostics) = l.lex_tokens();
+    let codes: Vec<&str> = diagnostics.iter().map(|d| d.code).collect();
+
+    assert!(tokens.is_empty());
+    assert_eq!(codes, vec!["E0009", "E0010", "E0011", "E0009", "E0012"]);
+    assert_eq!(
+        diagnostics[0].message,
+        "integer literal out of range for int (i64)"
+    );
+    assert_eq!(diagnostics[1].span.start, 25);
+}
+
+#[test]
+fn test_compound_assignment_lexing() {
+    let mut l = Lexer::from_string(String::from("+= -= *= /= %= **= ** *"));
+
+    let (tokens, _) = l.lex_tokens();
+    let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+
+    assert_eq!(
+        types,
+        vec![
+            TokenType::Plusequal,
+            TokenType::Minusequal,
+            TokenType::Starequal,
+            TokenType::Slashequal,
+            TokenType::Remainderequal,
+            TokenType::Powerequal,
+            TokenType::Power,
+            TokenType::Star,
+        ]
+    );
+}
+
+#[test]
+fn test_lossless_tokens() {
+    let source = "// leading comment\r\n/// docs\nfn main() { // trailing\n    int   a = 1; /* block */\n    $ \"é\\n\";\n}\n\n// end\n";
+    let (tokens, diagnostics) = Lexer::from_string(source.to_string()).lex_lossless();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(tokens.to_string(), source);
+
+    // the parser gets the same tokens as from `lex_tokens`
+    let (plain, _) = Lexer::from_string(source.to_string()).lex_tokens();
+    let types: Vec<_> = tokens
+        .parser_tokens()
+        .iter()
+        .map(|t| t.token_type)
+        .collect();
+    assert_eq!(
+        types,
+        plain.iter().map(|t| t.token_type).collect::<Vec<_>>()
+    );
+
+    let doc = &tokens.tokens[0];
+    assert_eq!(doc.token.token_type, TokenType::DocComment);
+    assert_eq!(doc.leading[0].kind, TriviaKind::LineComment);
+    assert_eq!(doc.leading[0].text, "// leading comment");
+    assert_eq!(doc.leading[1].kind, TriviaKind::Whitespace);
+
+    let curl = &tokens.tokens[5];
+    assert_eq!(curl.text, "{");
+    let kinds: Vec<_> = curl.trailing.iter().map(|t| t.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TriviaKind::Whitespace,
+            TriviaKind::LineComment,
+            TriviaKind::Newline
+        ]
+    );
+
+    // the invalid char is skipped, but kept in front of the string
+    let string = &tokens.tokens[11];
+    assert_eq!(string.text, "\"é\\n\"");
+    assert_eq!(string.token.lexeme, "é\n");
+    assert_eq!(string.leading[1].kind, TriviaKind::Skipped);
+
+    assert_eq!(tokens.end.len(), 3);
+    assert_eq!(tokens.end[1].kind, TriviaKind::LineComment);
+}
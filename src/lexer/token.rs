@@ -28,13 +28,23 @@ pub enum TokenType {
     Lessequal,
     And,
     Or,
+    Plusequal,
+    Minusequal,
+    Starequal,
+    Slashequal,
+    Remainderequal,
+    Powerequal,
 
     // Literals.
     Identifier,
     String,
+    Char,
     Int,
     Double,
 
+    // Documentation comment, the lexeme is the text after `///`.
+    DocComment,
+
     // Keywords.
     Class,
     Else,
@@ -42,6 +52,7 @@ pub enum TokenType {
     Fn,
     For,
     If,
+    New,
     Null,
     Return,
     Super,
@@ -51,12 +62,37 @@ pub enum TokenType {
     In,
 }
 
+impl TokenType {
+    /// the binary operator behind a compound assignment like `+=`
+    pub fn compound_operator(self) -> Option<TokenType> {
+        match self {
+            TokenType::Plusequal => Some(TokenType::Plus),
+            TokenType::Minusequal => Some(TokenType::Minus),
+            TokenType::Starequal => Some(TokenType::Star),
+            TokenType::Slashequal => Some(TokenType::Slash),
+            TokenType::Remainderequal => Some(TokenType::Remainder),
+            TokenType::Powerequal => Some(TokenType::Power),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct TokenSpan {
     pub start: usize, // inclusive
     pub end: usize,   // exclusive
 }
 
+impl TokenSpan {
+    /// span from the start of self to the end of other
+    pub fn to(self, other: TokenSpan) -> TokenSpan {
+        TokenSpan {
+            start: self.start,
+            end: other.end,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Token {
     pub token_type: TokenType,
@@ -0,0 +1,69 @@
+use std::fmt;
+
+use super::token::{Token, TokenSpan};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriviaKind {
+    // spaces, tabs and carriage returns
+    Whitespace,
+    Newline,
+    LineComment,
+    BlockComment,
+    // the text of a lexical error, which didn't become a token
+    Skipped,
+}
+
+/// text between tokens, which the parser never sees
+#[derive(Debug, Clone)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub span: TokenSpan,
+}
+
+/// a token with its original text and the trivia around it, the trailing
+/// trivia goes up to and including the end of the line, everything after
+/// that leads the next token
+#[derive(Debug, Clone)]
+pub struct SyntaxToken {
+    pub token: Token,
+    // the lexeme of a literal is its value, this is what was written
+    pub text: String,
+    pub leading: Vec<Trivia>,
+    pub trailing: Vec<Trivia>,
+}
+
+/// every token of a source with its trivia, printing it reproduces the source exactly
+#[derive(Debug, Clone, Default)]
+pub struct SyntaxTokens {
+    pub tokens: Vec<SyntaxToken>,
+    // trivia behind the last token
+    pub end: Vec<Trivia>,
+}
+
+impl SyntaxTokens {
+    /// the tokens without trivia, as the parser takes them
+    pub fn parser_tokens(&self) -> Vec<Token> {
+        self.tokens.iter().map(|t| t.token.clone()).collect()
+    }
+}
+
+impl fmt::Display for SyntaxTokens {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in &self.tokens {
+            for trivia in &token.leading {
+                write!(f, "{}", trivia.text)?;
+            }
+            write!(f, "{}", token.text)?;
+            for trivia in &token.trailing {
+                write!(f, "{}", trivia.text)?;
+            }
+        }
+
+        for trivia in &self.end {
+            write!(f, "{}", trivia.text)?;
+        }
+
+        Ok(())
+    }
+}
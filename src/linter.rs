@@ -0,0 +1,530 @@
+use std::collections::HashSet;
+
+use crate::checker::Checker;
+use crate::lexer::token::{Token, TokenSpan, TokenType};
+use crate::parser::ast::{Decl, Expr, FunctionDecl, Program, Stmt, VarDecl};
+use crate::reporter::diagnostic::{Diagnostic, Severity};
+
+/// a check for code which is valid, but most likely a mistake
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lint {
+    UnusedVariable,
+    UnusedFunction,
+    UnreachableCode,
+    Shadowing,
+    ConstantCondition,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 5] = [
+        Lint::UnusedVariable,
+        Lint::UnusedFunction,
+        Lint::UnreachableCode,
+        Lint::Shadowing,
+        Lint::ConstantCondition,
+    ];
+
+    /// the name used by `-W`, `-A` and `-D`
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "unused-variable",
+            Lint::UnusedFunction => "unused-function",
+            Lint::UnreachableCode => "unreachable-code",
+            Lint::Shadowing => "shadowing",
+            Lint::ConstantCondition => "constant-condition",
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "W0001",
+            Lint::UnusedFunction => "W0002",
+            Lint::UnreachableCode => "W0003",
+            Lint::Shadowing => "W0004",
+            Lint::ConstantCondition => "W0005",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// the level of every lint, all of them warn by default
+#[derive(Debug, Clone)]
+pub struct Levels {
+    levels: [Level; Lint::ALL.len()],
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Levels {
+            levels: [Level::Warn; Lint::ALL.len()],
+        }
+    }
+}
+
+impl Levels {
+    pub fn set(&mut self, lint: Lint, level: Level) {
+        self.levels[lint as usize] = level;
+    }
+
+    pub fn get(&self, lint: Lint) -> Level {
+        self.levels[lint as usize]
+    }
+
+    /// turns every warning into an error, for `--deny-warnings`
+    pub fn deny_warnings(&mut self) {
+        for level in &mut self.levels {
+            if *level == Level::Warn {
+                *level = Level::Deny;
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Local {
+    name: Token,
+    used: bool,
+}
+
+/// walks a program which passed the checker and reports lints,
+/// a denied lint is reported as an error
+pub fn lint(program: &Program, levels: &Levels) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        levels,
+        globals: HashSet::new(),
+        scopes: Vec::new(),
+        called: HashSet::new(),
+        diagnostics: Vec::new(),
+    };
+
+    for decl in &program.declarations {
+        if let Decl::Var(var) = decl {
+            linter.globals.insert(var.name.lexeme.clone());
+        }
+    }
+
+    for decl in &program.declarations {
+        match decl {
+            Decl::Function(function) => linter.function(function),
+            Decl::Class(class) => {
+                for field in &class.fields {
+                    linter.initializer(field);
+                }
+                for method in class.constructor.iter().chain(&class.methods) {
+                    linter.function(method);
+                }
+            }
+            Decl::Var(var) => linter.initializer(var),
+        }
+    }
+
+    for decl in &program.declarations {
+        if let Decl::Function(function) = decl {
+            let name = &function.name;
+
+            if name.lexeme != "main" && !linter.called.contains(&name.lexeme) {
+                linter.warn(
+                    Lint::UnusedFunction,
+                    format!("function '{}' is never called", name.lexeme),
+                    name.span,
+                    "defined here",
+                );
+            }
+        }
+    }
+
+    linter.diagnostics.sort_by_key(|d| d.span.start);
+    linter.diagnostics
+}
+
+struct Linter<'a> {
+    levels: &'a Levels,
+    globals: HashSet<String>,
+    // locals of the current function, the innermost scope is last
+    scopes: Vec<Vec<Local>>,
+    called: HashSet<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter<'_> {
+    fn function(&mut self, function: &FunctionDecl) {
+        self.scopes.push(Vec::new());
+        for param in &function.params {
+            self.define(&param.name);
+        }
+
+        self.statements(&function.body);
+        self.end_scope();
+    }
+
+    fn statements(&mut self, statements: &[Stmt]) {
+        for (i, stmt) in statements.iter().enumerate() {
+            self.statement(stmt);
+
+            if Checker::always_returns(std::slice::from_ref(stmt)) {
+                if let Some(span) = statements[i + 1..].iter().find_map(span) {
+                    self.warn(
+                        Lint::UnreachableCode,
+                        "unreachable statement".to_string(),
+                        span,
+                        "the function has already returned",
+                    );
+                }
+                // the rest is still walked, so its variables count as used
+                for stmt in &statements[i + 1..] {
+                    self.statement(stmt);
+                }
+                return;
+            }
+        }
+    }
+
+    fn statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression(expr) => self.expression(expr),
+            Stmt::Var(var) => {
+                self.initializer(var);
+                self.define(&var.name);
+            }
+            Stmt::Block(statements) => {
+                self.scopes.push(Vec::new());
+                self.statements(statements);
+                self.end_scope();
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.condition(condition, false);
+                self.statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            Stmt::While { condition, body } => {
+                self.condition(condition, true);
+                self.statement(body);
+            }
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+            } => {
+                self.scopes.push(Vec::new());
+                if let Some(initializer) = initializer {
+                    self.statement(initializer);
+                }
+                if let Some(condition) = condition {
+                    self.condition(condition, true);
+                }
+                if let Some(increment) = increment {
+                    self.expression(increment);
+                }
+                self.statement(body);
+                self.end_scope();
+            }
+            Stmt::ForIn {
+                name,
+                iterable,
+                body,
+            } => {
+                self.expression(iterable);
+
+                self.scopes.push(Vec::new());
+                self.define(name);
+                self.statement(body);
+                self.end_scope();
+            }
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.expression(value);
+                }
+            }
+        }
+    }
+
+    fn initializer(&mut self, var: &VarDecl) {
+        if let Some(initializer) = &var.initializer {
+            self.expression(initializer);
+        }
+    }
+
+    /// `while (true)` is how an endless loop is written, so only loops
+    /// which never run are reported
+    fn condition(&mut self, condition: &Expr, is_loop: bool) {
+        self.expression(condition);
+
+        match constant(condition) {
+            Some(true) if is_loop => {}
+            Some(value) => self.warn(
+                Lint::ConstantCondition,
+                format!("condition is always {}", value),
+                condition.span(),
+                "this doesn't depend on anything",
+            ),
+            None => {}
+        }
+    }
+
+    fn expression(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expr::Unary { right, .. } => self.expression(right),
+            Expr::Grouping(expr) => self.expression(expr),
+            Expr::Variable(name) => self.use_variable(name),
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                match callee.as_ref() {
+                    // functions and variables don't share names
+                    Expr::Variable(name) => {
+                        self.called.insert(name.lexeme.clone());
+                    }
+                    callee => self.expression(callee),
+                }
+                self.expressions(arguments);
+            }
+            Expr::Get { object, .. } => self.expression(object),
+            Expr::Assign {
+                name,
+                operator,
+                value,
+            } => {
+                // a plain assignment doesn't read the variable
+                if operator.token_type != TokenType::Equal {
+                    self.use_variable(name);
+                }
+                self.expression(value);
+            }
+            Expr::Set { object, value, .. } => {
+                self.expression(object);
+                self.expression(value);
+            }
+            Expr::Index { object, index, .. } => {
+                self.expression(object);
+                self.expression(index);
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.expression(object);
+                self.expression(index);
+                self.expression(value);
+            }
+            Expr::Array(elements, _) => self.expressions(elements),
+            Expr::Builtin { arguments, .. } | Expr::New { arguments, .. } => {
+                self.expressions(arguments)
+            }
+            Expr::This(_)
+            | Expr::LiteralTrue(_)
+            | Expr::LiteralFalse(_)
+            | Expr::LiteralNull(_)
+            | Expr::LiteralInt(..)
+            | Expr::LiteralDouble(..)
+            | Expr::LiteralString(..)
+            | Expr::LiteralChar(..) => {}
+        }
+    }
+
+    fn expressions(&mut self, expressions: &[Expr]) {
+        for expr in expressions {
+            self.expression(expr);
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        let shadows = self
+            .scopes
+            .iter()
+            .flatten()
+            .any(|local| local.name.lexeme == name.lexeme)
+            || self.globals.contains(&name.lexeme);
+
+        if shadows {
+            self.warn(
+                Lint::Shadowing,
+                format!("'{}' shadows a variable of an enclosing scope", name.lexeme),
+                name.span,
+                "the other variable can't be used here",
+            );
+        }
+
+        self.scopes.last_mut().unwrap().push(Local {
+            name: name.clone(),
+            used: false,
+        });
+    }
+
+    fn use_variable(&mut self, name: &Token) {
+        let local = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|local| local.name.lexeme == name.lexeme);
+
+        if let Some(local) = local {
+            local.used = true;
+        }
+    }
+
+    fn end_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap();
+
+        // like in Rust, a leading '_' marks a variable as intentionally unused
+        for local in scope {
+            if !local.used && !local.name.lexeme.starts_with('_') {
+                self.warn(
+                    Lint::UnusedVariable,
+                    format!("variable '{}' is never used", local.name.lexeme),
+                    local.name.span,
+                    "declared here",
+                );
+            }
+        }
+    }
+
+    fn warn(&mut self, lint: Lint, message: String, span: TokenSpan, label: &str) {
+        let severity = match self.levels.get(lint) {
+            Level::Allow => return,
+            Level::Warn => Severity::Warning,
+            Level::Deny => Severity::Error,
+        };
+
+        self.diagnostics.push(Diagnostic {
+            severity,
+            ..Diagnostic::warning(lint.code(), message, span).with_label(label)
+        });
+    }
+}
+
+/// the value of a condition which doesn't depend on anything
+fn constant(expr: &Expr) -> Option<bool> {
+    match expr {
+        Expr::LiteralTrue(_) => Some(true),
+        Expr::LiteralFalse(_) => Some(false),
+        Expr::Grouping(expr) => constant(expr),
+        Expr::Unary { operator, right } if operator.token_type == TokenType::Bang => {
+            constant(right).map(|value| !value)
+        }
+        Expr::Logical {
+            left,
+            operator,
+            right,
+        } => {
+            let (left, right) = (constant(left)?, constant(right)?);
+
+            match operator.token_type {
+                TokenType::And => Some(left && right),
+                _ => Some(left || right),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// where a statement starts, an empty block has no span
+fn span(stmt: &Stmt) -> Option<TokenSpan> {
+    match stmt {
+        Stmt::Expression(expr) => Some(expr.span()),
+        Stmt::Var(var) => Some(var.name.span),
+        Stmt::Block(statements) => statements.iter().find_map(span),
+        Stmt::If { condition, .. } | Stmt::While { condition, .. } => Some(condition.span()),
+        Stmt::For {
+            initializer,
+            condition,
+            body,
+            ..
+        } => initializer
+            .as_deref()
+            .and_then(span)
+            .or(condition.as_ref().map(Expr::span))
+            .or_else(|| span(body)),
+        Stmt::ForIn { name, .. } => Some(name.span),
+        Stmt::Return { keyword, .. } => Some(keyword.span),
+    }
+}
+
+#[cfg(test)]
+fn lint_source(source: &str, levels: &Levels) -> Vec<(Severity, &'static str, String)> {
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    let tokens = Lexer::from_string(source.to_string()).lex_tokens().0;
+    let program = Parser::new(tokens).parse().unwrap();
+    Checker::new().check(&program).unwrap();
+
+    lint(&program, levels)
+        .into_iter()
+        .map(|d| (d.severity, d.code, d.message))
+        .collect()
+}
+
+#[test]
+fn test_lints() {
+    let source = "
+        int count = 0;
+
+        fn unused(int a, int _b) {
+            int c = 1;
+            c = 2;
+            for x in [1, 2] {
+                count += 1;
+            }
+        }
+
+        fn early() > int {
+            return 1;
+            out!(count);
+        }
+
+        fn main() {
+            int count = early();
+            if (!false) {
+                out!(count);
+            }
+            while (true) {
+                return;
+            }
+        }";
+
+    let warnings = lint_source(source, &Levels::default());
+    let codes: Vec<_> = warnings.iter().map(|w| w.1).collect();
+    assert_eq!(
+        codes,
+        vec!["W0002", "W0001", "W0001", "W0001", "W0003", "W0004", "W0005"]
+    );
+    assert_eq!(warnings[0].2, "function 'unused' is never called");
+    assert_eq!(warnings[2].2, "variable 'c' is never used");
+    assert_eq!(warnings[6].2, "condition is always true");
+    assert!(warnings.iter().all(|w| w.0 == Severity::Warning));
+
+    let mut levels = Levels::default();
+    levels.set(Lint::UnusedVariable, Level::Allow);
+    levels.set(Lint::Shadowing, Level::Deny);
+    let diagnostics = lint_source(source, &levels);
+    assert_eq!(diagnostics.len(), 4);
+    assert_eq!(diagnostics[2].0, Severity::Error);
+
+    levels.deny_warnings();
+    let diagnostics = lint_source(source, &levels);
+    assert!(diagnostics.iter().all(|d| d.0 == Severity::Error));
+}
@@ -1,33 +1,433 @@
+mod builtins;
+mod checker;
+mod formatter;
+mod interpreter;
 mod lexer;
+mod linter;
 mod parser;
+mod repl;
 mod reporter;
+mod vm;
 
+use std::io;
+
+use checker::Checker;
+use interpreter::value::Value;
+use interpreter::Interpreter;
+use lexer::token::{Token, TokenSpan};
+use lexer::trivia::{SyntaxTokens, Trivia};
 use lexer::Lexer;
+use linter::{Level, Lint};
 use parser::Parser;
+use reporter::lines::Lines;
+use reporter::{ErrorFormat, Reporter};
+use vm::disassembler::disassemble;
+use vm::Vm;
+
+const USAGE: &str = "Usage: rawk [--engine=tree|vm] [--gc-stats] \
+                     [--dump-tokens|--dump-trivia|--dump-ast|--dump-bytecode] \
+                     [--error-format=human|json] [-W|-A|-D lint] [--deny-warnings] \
+                     [filename|-]\n       rawk --explain <code>\n       \
+                     rawk fmt [--check] [--indent=N] [--line-length=N] [filename...|-]";
+
+/// how the program is executed
+#[derive(Debug, Default, PartialEq)]
+enum Engine {
+    /// walks the syntax tree
+    #[default]
+    Tree,
+    /// compiles to bytecode which is run by a stack machine
+    Vm,
+}
+
+/// stage of the pipeline whose output is printed instead of running the program
+#[derive(Debug, PartialEq)]
+enum Dump {
+    Tokens,
+    // the tokens with all whitespace and comments
+    Trivia,
+    Ast,
+    Bytecode,
+}
+
+/// command line arguments
+#[derive(Debug, Default)]
+struct Options {
+    // without a file the REPL is started
+    path: Option<String>,
+    engine: Engine,
+    gc_stats: bool,
+    dump: Option<Dump>,
+    error_format: ErrorFormat,
+    lints: linter::Levels,
+    // the code to explain instead of running a program
+    explain: Option<String>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Option<Options> {
+        let mut options = Options::default();
+        let mut deny_warnings = false;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--gc-stats" => options.gc_stats = true,
+                "--engine=tree" => options.engine = Engine::Tree,
+                "--engine=vm" => options.engine = Engine::Vm,
+                "--dump-tokens" => options.dump = Some(Dump::Tokens),
+                "--dump-trivia" => options.dump = Some(Dump::Trivia),
+                "--dump-ast" => options.dump = Some(Dump::Ast),
+                "--dump-bytecode" => options.dump = Some(Dump::Bytecode),
+                "--error-format=human" => options.error_format = ErrorFormat::Human,
+                "--error-format=json" => options.error_format = ErrorFormat::Json,
+                "--deny-warnings" => deny_warnings = true,
+                "--explain" => options.explain = Some(args.next()?),
+                // the lint name either follows directly, like `-Wshadowing`, or is the next argument
+                _ if ["-W", "-A", "-D"].iter().any(|flag| arg.starts_with(flag)) => {
+                    let level = match &arg[..2] {
+                        "-W" => Level::Warn,
+                        "-A" => Level::Allow,
+                        _ => Level::Deny,
+                    };
+                    let name = match &arg[2..] {
+                        "" => args.next()?,
+                        name => name.to_string(),
+                    };
+
+                    options.lints.set(Lint::from_name(&name)?, level);
+                }
+                _ if arg.len() > 1 && arg.starts_with('-') => return None,
+                _ if options.path.is_some() => return None,
+                _ => options.path = Some(arg),
+            }
+        }
+
+        if deny_warnings {
+            options.lints.deny_warnings();
+        }
+
+        Some(options)
+    }
+}
+
+/// arguments of `rawk fmt`
+#[derive(Debug, Default)]
+struct FmtOptions {
+    // without a file stdin is formatted to stdout
+    paths: Vec<String>,
+    // only report unformatted files instead of rewriting them
+    check: bool,
+    config: formatter::Config,
+}
+
+impl FmtOptions {
+    fn parse(args: impl Iterator<Item = String>) -> Option<FmtOptions> {
+        let mut options = FmtOptions::default();
+
+        for arg in args {
+            match arg.as_str() {
+                "--check" => options.check = true,
+                _ if arg.starts_with("--indent=") => {
+                    options.config.indent = arg["--indent=".len()..].parse().ok()?
+                }
+                _ if arg.starts_with("--line-length=") => {
+                    options.config.line_length = arg["--line-length=".len()..].parse().ok()?
+                }
+                _ if arg.len() > 1 && arg.starts_with('-') => return None,
+                _ => options.paths.push(arg),
+            }
+        }
+
+        Some(options)
+    }
+}
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
+    // the default stack of the main thread overflows long before the call depth limit
+    let thread = std::thread::Builder::new()
+        .name("main".to_string())
+        .stack_size(interpreter::STACK_SIZE);
+    let result = thread
+        .spawn(run)
+        .expect("could not start the interpreter thread")
+        .join();
+
+    if let Err(panic) = result {
+        std::panic::resume_unwind(panic);
+    }
+}
 
-    if args.len() != 2 {
-        println!("Usage: rawk <filename>");
+fn run() {
+    let mut args = std::env::args().skip(1).peekable();
+
+    if args.peek().is_some_and(|arg| arg == "fmt") {
+        match FmtOptions::parse(args.skip(1)) {
+            Some(options) => std::process::exit(run_fmt(&options)),
+            None => {
+                println!("{}", USAGE);
+                return;
+            }
+        }
+    }
+
+    let options = match Options::parse(args) {
+        Some(options) => options,
+        None => {
+            println!("{}", USAGE);
+            return;
+        }
+    };
+
+    if let Some(code) = &options.explain {
+        match reporter::codes::explain(code) {
+            Some(explanation) => println!("{}", explanation),
+            None => {
+                eprintln!("Error: '{}' is not a known diagnostic code", code);
+                std::process::exit(-1);
+            }
+        }
         return;
     }
 
-    run_file(&args[1]);
+    match &options.path {
+        Some(path) => run_file(path, &options),
+        None => repl::run(options.error_format),
+    }
 }
 
-fn run_file(path: &str) {
-    let mut lexer = Lexer::from_file(path);
+fn run_file(path: &str, options: &Options) {
+    let mut reporter = Reporter::new(options.error_format);
+    let sources = &mut reporter.sources;
 
-    let tokens = lexer.lex_tokens();
+    // `-` reads the program from stdin
+    let loaded = if path == "-" {
+        io::read_to_string(io::stdin()).map(|text| sources.add("<stdin>", text))
+    } else {
+        sources.load(path)
+    };
+    let file = match loaded {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("Error: could not read '{}': {}", path, err);
+            std::process::exit(-1);
+        }
+    };
 
-    if tokens.is_none() {
+    let source = reporter.sources.text(file);
+    let mut lexer = Lexer::from_string(source.to_string());
+
+    let (tokens, diagnostics) = match options.dump {
+        Some(Dump::Trivia) => {
+            let (syntax, diagnostics) = lexer.lex_lossless();
+            dump_trivia(&syntax, source);
+            (syntax.parser_tokens(), diagnostics)
+        }
+        _ => lexer.lex_tokens(),
+    };
+
+    for diagnostic in &diagnostics {
+        reporter.report(diagnostic, file);
+    }
+
+    if options.dump == Some(Dump::Tokens) {
+        dump_tokens(&tokens, source);
+    }
+
+    if diagnostics.iter().any(|d| d.is_error()) {
         std::process::exit(-1);
     }
 
-    let tokens = tokens.unwrap();
+    if matches!(options.dump, Some(Dump::Tokens | Dump::Trivia)) {
+        return;
+    }
+
+    let mut parser = Parser::new(tokens);
+
+    let program = match parser.parse() {
+        Ok(program) => program,
+        Err(errors) => {
+            for error in errors {
+                reporter.report_error(error.code, &error.message, file, error.span, None);
+            }
+            std::process::exit(-1);
+        }
+    };
+
+    if options.dump == Some(Dump::Ast) {
+        print!("{}", parser::printer::print(&program));
+        return;
+    }
+
+    if let Err(errors) = Checker::new().check(&program) {
+        for error in errors {
+            reporter.report_error(error.code, &error.message, file, error.span, None);
+        }
+        std::process::exit(-1);
+    }
+
+    let lints = linter::lint(&program, &options.lints);
+    for diagnostic in &lints {
+        reporter.report(diagnostic, file);
+    }
+
+    if lints.iter().any(|d| d.is_error()) {
+        std::process::exit(-1);
+    }
+
+    // dumping the bytecode needs it compiled, whatever engine is selected
+    let engine = match options.dump {
+        Some(Dump::Bytecode) => &Engine::Vm,
+        _ => &options.engine,
+    };
+
+    let result = match engine {
+        Engine::Tree => {
+            let mut interpreter = Interpreter::new();
+            let result = interpreter.interpret(&program);
+
+            if options.gc_stats {
+                eprintln!("{}", interpreter.gc_stats());
+            }
+            result
+        }
+        Engine::Vm => {
+            let bytecode = match vm::compiler::compile(&program) {
+                Ok(bytecode) => bytecode,
+                Err(err) => {
+                    reporter.report_error(err.code, &err.message, file, err.span, None);
+                    std::process::exit(-1);
+                }
+            };
+
+            if options.dump == Some(Dump::Bytecode) {
+                print!("{}", disassemble(&bytecode, source));
+                return;
+            }
+
+            let mut vm = Vm::new();
+            let result = vm.run(&bytecode);
+
+            if options.gc_stats {
+                eprintln!("{}", vm.gc_stats());
+            }
+            result
+        }
+    };
+
+    match result {
+        // the return value of an `int main()` becomes the exit code
+        Ok(Value::Int(code)) => std::process::exit(code as i32),
+        Ok(_) => {}
+        Err(err) => {
+            reporter.report(&err.diagnostic(), file);
+            std::process::exit(-1);
+        }
+    }
+}
+
+/// formats every file in place, or with `--check` only reports the ones which
+/// aren't formatted. returns the exit code, 1 if a file needs formatting and
+/// -1 if a file couldn't be formatted at all
+fn run_fmt(options: &FmtOptions) -> i32 {
+    let mut reporter = Reporter::new(ErrorFormat::Human);
+    let mut status = 0;
+
+    let stdin = vec!["-".to_string()];
+    let paths = match options.paths.is_empty() {
+        true => &stdin,
+        false => &options.paths,
+    };
+
+    for path in paths {
+        let sources = &mut reporter.sources;
+        let loaded = if path == "-" {
+            io::read_to_string(io::stdin()).map(|text| sources.add("<stdin>", text))
+        } else {
+            sources.load(path)
+        };
+        let file = match loaded {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("Error: could not read '{}': {}", path, err);
+                status = -1;
+                continue;
+            }
+        };
+
+        let source = reporter.sources.text(file);
+        let (tokens, diagnostics) = Lexer::from_string(source.to_string()).lex_lossless();
+
+        for diagnostic in &diagnostics {
+            reporter.report(diagnostic, file);
+        }
+        if diagnostics.iter().any(|d| d.is_error()) {
+            status = -1;
+            continue;
+        }
+
+        // the formatter relies on the structure of a valid program
+        if let Err(errors) = Parser::new(tokens.parser_tokens()).parse() {
+            for error in errors {
+                reporter.report_error(error.code, &error.message, file, error.span, None);
+            }
+            status = -1;
+            continue;
+        }
+
+        let formatted = formatter::format(&tokens, &options.config);
+
+        if options.check {
+            if formatted != source {
+                eprintln!("Would reformat: {}", reporter.sources.name(file));
+                if status == 0 {
+                    status = 1;
+                }
+            }
+        } else if path == "-" {
+            print!("{}", formatted);
+        } else if formatted != source {
+            if let Err(err) = std::fs::write(path, formatted) {
+                eprintln!("Error: could not write '{}': {}", path, err);
+                status = -1;
+            }
+        }
+    }
+
+    status
+}
+
+/// prints every token with its position, the type and the lexeme
+fn dump_tokens(tokens: &[Token], source: &str) {
+    let lines = Lines::new(source);
+
+    for token in tokens {
+        let token_type = format!("{:?}", token.token_type);
+        dump_line(&lines, token.span, &token_type, &token.lexeme);
+    }
+}
+
+/// prints the tokens as written in the source, with every piece of trivia
+/// on its own line, listed in source order
+fn dump_trivia(tokens: &SyntaxTokens, source: &str) {
+    let lines = Lines::new(source);
+    let trivia = |trivia: &[Trivia]| {
+        for t in trivia {
+            dump_line(&lines, t.span, &format!("{:?}", t.kind), &t.text);
+        }
+    };
+
+    for token in &tokens.tokens {
+        trivia(&token.leading);
+        let token_type = format!("{:?}", token.token.token_type);
+        dump_line(&lines, token.token.span, &token_type, &token.text);
+        trivia(&token.trailing);
+    }
+    trivia(&tokens.end);
+}
 
-    let mut parser = Parser::new(tokens, path.to_string());
+fn dump_line(lines: &Lines, span: TokenSpan, kind: &str, text: &str) {
+    let (line, column) = lines.position(span.start);
+    let position = format!("{}:{}", line, column);
 
-    parser.parse();
+    println!("{:<8} {:<14} {:?}", position, kind, text);
 }
@@ -1,8 +1,11 @@
-use crate::lexer::token::{Token, TokenType};
-use crate::reporter;
+pub mod ast;
+pub mod printer;
 
-#[cfg(test)]
-use crate::lexer::token::TokenSpan;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use self::ast::{ClassDecl, Decl, Entry, Expr, FunctionDecl, Param, Program, Stmt, Type, VarDecl};
+use crate::lexer::token::{Token, TokenSpan, TokenType};
 
 #[macro_export]
 macro_rules! match_tokens {
@@ -21,55 +24,544 @@ macro_rules! match_tokens {
 }
 
 #[derive(Debug)]
-pub enum Expr {
-    Binary {
-        left: Box<Expr>,
-        operator: Token,
-        right: Box<Expr>,
-    },
-    Unary {
-        operator: Token,
-        right: Box<Expr>,
-    },
-    Grouping(Box<Expr>),
-
-    // TODO: find better way of doing this (maybe seperate enum???)
-    LiteralTrue,
-    LiteralFalse,
-    LiteralNull,
-    LiteralInt(i64),
-    LiteralDouble(f64),
-    LiteralString(String),
+pub struct ParseError {
+    pub code: &'static str,
+    pub message: String,
+    pub span: TokenSpan,
 }
 
+type ParseResult<T> = Result<T, ParseError>;
+
 #[derive(Debug)]
 pub struct Parser {
     tokens: Vec<Token>,
-    file_path: String,
+    // doc comments by the index of the token they document
+    docs: HashMap<usize, String>,
     current: usize,
+    errors: Vec<ParseError>,
 }
 
 impl Parser {
-    pub fn new(tokens: Vec<Token>, file_path: String) -> Self {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        let mut filtered = Vec::with_capacity(tokens.len());
+        let mut docs = HashMap::new();
+        let mut doc_lines: Vec<String> = Vec::new();
+
+        for token in tokens {
+            if token.token_type == TokenType::DocComment {
+                doc_lines.push(token.lexeme);
+                continue;
+            }
+
+            if !doc_lines.is_empty() {
+                docs.insert(filtered.len(), doc_lines.join("\n"));
+                doc_lines.clear();
+            }
+
+            filtered.push(token);
+        }
+
         Parser {
-            tokens,
-            file_path,
+            tokens: filtered,
+            docs,
             current: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    /// parses the whole program, collecting every syntax error on the way
+    pub fn parse(&mut self) -> Result<Program, Vec<ParseError>> {
+        let mut declarations = Vec::new();
+
+        while !self.is_at_end() {
+            let start = self.current;
+
+            match self.declaration() {
+                Ok(decl) => declarations.push(decl),
+                Err(err) => {
+                    self.recover(err, start);
+
+                    // leftover braces of the broken declaration
+                    while match_tokens!(self, TokenType::Rightcurl) {}
+                }
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(Program { declarations })
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    /// parses the input of the REPL, a mix of declarations and statements
+    /// which may end in an expression without ';'
+    pub fn parse_entries(&mut self) -> Result<Vec<Entry>, Vec<ParseError>> {
+        let mut entries = Vec::new();
+
+        while !self.is_at_end() {
+            let start = self.current;
+
+            match self.entry() {
+                Ok(entry) => entries.push(entry),
+                Err(err) => self.recover(err, start),
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(entries)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    fn entry(&mut self) -> ParseResult<Entry> {
+        if self.check(TokenType::Fn) || self.check(TokenType::Class) || self.check_var_decl() {
+            return Ok(Entry::Decl(self.declaration()?));
+        }
+
+        match self.peek().token_type {
+            TokenType::If
+            | TokenType::While
+            | TokenType::For
+            | TokenType::Return
+            | TokenType::Leftcurl => Ok(Entry::Stmt(self.statement()?)),
+            _ => {
+                let expr = self.expression()?;
+
+                if self.is_at_end() {
+                    return Ok(Entry::Expr(expr));
+                }
+
+                self.consume_token(TokenType::Semicolon, "Expect ';' after expression.")?;
+                Ok(Entry::Stmt(Stmt::Expression(expr)))
+            }
+        }
+    }
+
+    fn declaration(&mut self) -> ParseResult<Decl> {
+        let doc = self.current_doc();
+
+        if match_tokens!(self, TokenType::Fn) {
+            return Ok(Decl::Function(Rc::new(self.function(doc)?)));
+        }
+
+        if match_tokens!(self, TokenType::Class) {
+            return Ok(Decl::Class(Rc::new(self.class_declaration(doc)?)));
+        }
+
+        if self.check_var_decl() {
+            return Ok(Decl::Var(Box::new(self.var_declaration()?)));
+        }
+
+        Err(self.error_at_current("E0103", "Expected declaration."))
+    }
+
+    fn class_declaration(&mut self, doc: Option<String>) -> ParseResult<ClassDecl> {
+        let name = self.consume_token(TokenType::Identifier, "Expect class name.")?;
+        self.consume_token(TokenType::Leftcurl, "Expect '{' before class body.")?;
+
+        let mut fields = Vec::new();
+        let mut constructor = None;
+        let mut methods = Vec::new();
+
+        while !self.check(TokenType::Rightcurl) && !self.is_at_end() {
+            let start = self.current;
+
+            if let Err(err) = self.class_member(&name, &mut fields, &mut constructor, &mut methods)
+            {
+                self.recover(err, start);
+            }
+        }
+
+        self.consume_token(TokenType::Rightcurl, "Expect '}' after class body.")?;
+
+        Ok(ClassDecl {
+            doc,
+            name,
+            fields,
+            constructor,
+            methods,
+        })
+    }
+
+    fn class_member(
+        &mut self,
+        class_name: &Token,
+        fields: &mut Vec<VarDecl>,
+        constructor: &mut Option<Rc<FunctionDecl>>,
+        methods: &mut Vec<Rc<FunctionDecl>>,
+    ) -> ParseResult<()> {
+        let doc = self.current_doc();
+
+        // constructors can be written as `Test() {}` or `fn Test() {}`
+        let is_constructor = (self.check(TokenType::Identifier)
+            && self.peek().lexeme == class_name.lexeme
+            && self.check_next(TokenType::Leftparen))
+            || (self.check(TokenType::Fn)
+                && self.tokens.get(self.current + 1).map(|t| t.lexeme.as_str())
+                    == Some(class_name.lexeme.as_str()));
+
+        if is_constructor {
+            match_tokens!(self, TokenType::Fn);
+
+            let function = self.function(doc)?;
+            if constructor.is_some() {
+                return Err(self.error(
+                    "E0105",
+                    &function.name,
+                    "Class already has a constructor.",
+                ));
+            }
+            if function.return_type != Type::Void {
+                return Err(self.error(
+                    "E0105",
+                    &function.name,
+                    "Constructors can't have a return type.",
+                ));
+            }
+
+            *constructor = Some(Rc::new(function));
+        } else if match_tokens!(self, TokenType::Fn) {
+            methods.push(Rc::new(self.function(doc)?));
+        } else if self.check_var_decl() {
+            fields.push(self.var_declaration()?);
+        } else {
+            return Err(self.error_at_current("E0103", "Expect field, constructor or method."));
+        }
+
+        Ok(())
+    }
+
+    fn function(&mut self, doc: Option<String>) -> ParseResult<FunctionDecl> {
+        let name = self.consume_token(TokenType::Identifier, "Expect function name.")?;
+        self.consume_token(TokenType::Leftparen, "Expect '(' after function name.")?;
+
+        let mut params = Vec::new();
+        if !self.check(TokenType::Rightparen) {
+            loop {
+                let ty = self.parse_type()?;
+                let name = self.consume_token(TokenType::Identifier, "Expect parameter name.")?;
+                params.push(Param { ty, name });
+
+                if !match_tokens!(self, TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+        self.consume_token(TokenType::Rightparen, "Expect ')' after parameters.")?;
+
+        let return_type = if match_tokens!(self, TokenType::Greater) {
+            self.parse_type()?
+        } else {
+            Type::Void
+        };
+
+        self.consume_token(TokenType::Leftcurl, "Expect '{' before function body.")?;
+        let body = self.block()?;
+
+        Ok(FunctionDecl {
+            doc,
+            name,
+            params,
+            return_type,
+            body,
+        })
+    }
+
+    fn parse_type(&mut self) -> ParseResult<Type> {
+        let token = self.consume_token(TokenType::Identifier, "Expect type.")?;
+
+        let mut ty = match token.lexeme.as_str() {
+            "int" => Type::Int,
+            "double" => Type::Double,
+            "bool" => Type::Bool,
+            "char" => Type::Char,
+            "String" => Type::String,
+            // everything else has to be a class, which is checked later
+            _ => Type::Class(token.lexeme),
+        };
+
+        while match_tokens!(self, TokenType::Leftbrack) {
+            self.consume_token(TokenType::Rightbrack, "Expect ']' after '['.")?;
+            ty = Type::Array(Box::new(ty));
+        }
+
+        Ok(ty)
+    }
+
+    /// a variable declaration starts with a type followed by the variable name,
+    /// `[]` can't start an expression after an identifier, so it also means a type
+    fn check_var_decl(&self) -> bool {
+        self.check(TokenType::Identifier)
+            && (self.check_next(TokenType::Identifier)
+                || self.check_nth(1, TokenType::Leftbrack)
+                    && self.check_nth(2, TokenType::Rightbrack))
+    }
+
+    fn var_declaration(&mut self) -> ParseResult<VarDecl> {
+        let ty = self.parse_type()?;
+        let name = self.consume_token(TokenType::Identifier, "Expect variable name.")?;
+
+        let initializer = if match_tokens!(self, TokenType::Equal) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+
+        self.consume_token(
+            TokenType::Semicolon,
+            "Expect ';' after variable declaration.",
+        )?;
+
+        Ok(VarDecl {
+            ty,
+            name,
+            initializer,
+        })
+    }
+
+    fn declaration_statement(&mut self) -> ParseResult<Stmt> {
+        if self.check_var_decl() {
+            return Ok(Stmt::Var(self.var_declaration()?));
+        }
+
+        self.statement()
+    }
+
+    fn statement(&mut self) -> ParseResult<Stmt> {
+        if match_tokens!(self, TokenType::If) {
+            return self.if_statement();
+        }
+        if match_tokens!(self, TokenType::While) {
+            return self.while_statement();
+        }
+        if match_tokens!(self, TokenType::For) {
+            return self.for_statement();
+        }
+        if match_tokens!(self, TokenType::Return) {
+            return self.return_statement();
+        }
+        if match_tokens!(self, TokenType::Leftcurl) {
+            return Ok(Stmt::Block(self.block()?));
         }
+
+        self.expression_statement()
+    }
+
+    fn block(&mut self) -> ParseResult<Vec<Stmt>> {
+        let mut statements = Vec::new();
+
+        // functions and classes can't be nested, so they mean a '}' is missing
+        while !self.check(TokenType::Rightcurl)
+            && !self.check(TokenType::Fn)
+            && !self.check(TokenType::Class)
+            && !self.is_at_end()
+        {
+            let start = self.current;
+
+            match self.declaration_statement() {
+                Ok(stmt) => statements.push(stmt),
+                Err(err) => self.recover(err, start),
+            }
+        }
+
+        self.consume_token(TokenType::Rightcurl, "Expect '}' after block.")?;
+
+        Ok(statements)
     }
 
-    pub fn parse(&mut self) {
-        let expr = self.expression();
+    fn if_statement(&mut self) -> ParseResult<Stmt> {
+        self.consume_token(TokenType::Leftparen, "Expect '(' after 'if'.")?;
+        let condition = self.expression()?;
+        self.consume_token(TokenType::Rightparen, "Expect ')' after if condition.")?;
 
-        println!("{:#?}", expr);
+        let then_branch = Box::new(self.statement()?);
+        let else_branch = if match_tokens!(self, TokenType::Else) {
+            Some(Box::new(self.statement()?))
+        } else {
+            None
+        };
+
+        Ok(Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+        })
+    }
+
+    fn while_statement(&mut self) -> ParseResult<Stmt> {
+        self.consume_token(TokenType::Leftparen, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume_token(TokenType::Rightparen, "Expect ')' after condition.")?;
+
+        let body = Box::new(self.statement()?);
+
+        Ok(Stmt::While { condition, body })
     }
 
-    fn equality(&mut self) -> Expr {
-        let mut expr = self.comparison();
+    fn for_statement(&mut self) -> ParseResult<Stmt> {
+        if self.check(TokenType::Identifier) && self.check_next(TokenType::In) {
+            return self.for_in_statement();
+        }
+
+        self.consume_token(TokenType::Leftparen, "Expect '(' after 'for'.")?;
+
+        let initializer = if match_tokens!(self, TokenType::Semicolon) {
+            None
+        } else if self.check_var_decl() {
+            Some(Box::new(Stmt::Var(self.var_declaration()?)))
+        } else {
+            Some(Box::new(self.expression_statement()?))
+        };
+
+        let condition = if self.check(TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume_token(TokenType::Semicolon, "Expect ';' after loop condition.")?;
+
+        let increment = if self.check(TokenType::Rightparen) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume_token(TokenType::Rightparen, "Expect ')' after for clauses.")?;
+
+        let body = Box::new(self.statement()?);
+
+        Ok(Stmt::For {
+            initializer,
+            condition,
+            increment,
+            body,
+        })
+    }
+
+    fn for_in_statement(&mut self) -> ParseResult<Stmt> {
+        let name = self.advance();
+        self.consume_token(TokenType::In, "Expect 'in' after loop variable.")?;
+
+        let iterable = self.expression()?;
+        let body = Box::new(self.statement()?);
+
+        Ok(Stmt::ForIn {
+            name,
+            iterable,
+            body,
+        })
+    }
+
+    fn return_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous();
+
+        let value = if self.check(TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+
+        self.consume_token(TokenType::Semicolon, "Expect ';' after return value.")?;
+
+        Ok(Stmt::Return { keyword, value })
+    }
+
+    fn expression_statement(&mut self) -> ParseResult<Stmt> {
+        let expr = self.expression()?;
+        self.consume_token(TokenType::Semicolon, "Expect ';' after expression.")?;
+
+        Ok(Stmt::Expression(expr))
+    }
+
+    fn assignment(&mut self) -> ParseResult<Expr> {
+        let expr = self.logic_or()?;
+
+        if match_tokens!(
+            self,
+            TokenType::Equal,
+            TokenType::Plusequal,
+            TokenType::Minusequal,
+            TokenType::Starequal,
+            TokenType::Slashequal,
+            TokenType::Remainderequal,
+            TokenType::Powerequal
+        ) {
+            let operator = self.previous();
+            let value = Box::new(self.assignment()?);
+
+            return match expr {
+                Expr::Variable(name) => Ok(Expr::Assign {
+                    name,
+                    operator,
+                    value,
+                }),
+                Expr::Get { object, name } => Ok(Expr::Set {
+                    object,
+                    name,
+                    operator,
+                    value,
+                }),
+                Expr::Index { object, index, .. } => Ok(Expr::SetIndex {
+                    object,
+                    index,
+                    operator,
+                    value,
+                }),
+                _ => {
+                    // the parser is not confused, so there is no need to synchronize
+                    self.errors.push(ParseError {
+                        code: "E0104",
+                        message: "Invalid assignment target.".to_string(),
+                        span: expr.span(),
+                    });
+
+                    Ok(expr)
+                }
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn logic_or(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.logic_and()?;
+
+        while match_tokens!(self, TokenType::Or) {
+            let op = self.previous();
+            let right = self.logic_and()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator: op,
+                right: Box::new(right),
+            }
+        }
+
+        Ok(expr)
+    }
+
+    fn logic_and(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.equality()?;
+
+        while match_tokens!(self, TokenType::And) {
+            let op = self.previous();
+            let right = self.equality()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator: op,
+                right: Box::new(right),
+            }
+        }
+
+        Ok(expr)
+    }
+
+    fn equality(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.comparison()?;
 
         while match_tokens!(self, TokenType::Bangequal, TokenType::Equalequal) {
             let op = self.previous();
-            let right = self.comparison();
+            let right = self.comparison()?;
             expr = Expr::Binary {
                 left: Box::new(expr),
                 operator: op,
@@ -77,11 +569,11 @@ impl Parser {
             }
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn comparison(&mut self) -> Expr {
-        let mut expr = self.term();
+    fn comparison(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.term()?;
 
         while match_tokens!(
             self,
@@ -91,7 +583,7 @@ impl Parser {
             TokenType::Lessequal
         ) {
             let op = self.previous();
-            let right = self.term();
+            let right = self.term()?;
             expr = Expr::Binary {
                 left: Box::new(expr),
                 operator: op,
@@ -99,15 +591,15 @@ impl Parser {
             }
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn term(&mut self) -> Expr {
-        let mut expr = self.factor();
+    fn term(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.factor()?;
 
         while match_tokens!(self, TokenType::Plus, TokenType::Minus) {
             let op = self.previous();
-            let right = self.factor();
+            let right = self.factor()?;
             expr = Expr::Binary {
                 left: Box::new(expr),
                 operator: op,
@@ -115,11 +607,11 @@ impl Parser {
             }
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn factor(&mut self) -> Expr {
-        let mut expr = self.unary();
+    fn factor(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.unary()?;
 
         while match_tokens!(
             self,
@@ -128,7 +620,7 @@ impl Parser {
             TokenType::Remainder
         ) {
             let op = self.previous();
-            let right = self.unary();
+            let right = self.unary()?;
             expr = Expr::Binary {
                 left: Box::new(expr),
                 operator: op,
@@ -136,93 +628,280 @@ impl Parser {
             }
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn unary(&mut self) -> Expr {
+    fn unary(&mut self) -> ParseResult<Expr> {
         if match_tokens!(self, TokenType::Bang, TokenType::Minus) {
             let op = self.previous();
-            let right = self.unary();
-            return Expr::Unary {
+            let right = self.unary()?;
+            return Ok(Expr::Unary {
                 operator: op,
                 right: Box::new(right),
-            };
+            });
+        }
+
+        self.power()
+    }
+
+    /// `**` binds tighter than unary operators and is right associative,
+    /// so `-2 ** 2` is `-(2 ** 2)` and `2 ** 3 ** 2` is `2 ** (3 ** 2)`
+    fn power(&mut self) -> ParseResult<Expr> {
+        let expr = self.call()?;
+
+        if match_tokens!(self, TokenType::Power) {
+            let op = self.previous();
+            // the exponent may have its own sign, like `2 ** -1`
+            let right = self.unary()?;
+            return Ok(Expr::Binary {
+                left: Box::new(expr),
+                operator: op,
+                right: Box::new(right),
+            });
+        }
+
+        Ok(expr)
+    }
+
+    fn call(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.primary()?;
+
+        loop {
+            if match_tokens!(self, TokenType::Leftparen) {
+                expr = self.finish_call(expr)?;
+            } else if match_tokens!(self, TokenType::Leftbrack) {
+                let index = self.expression()?;
+                let bracket =
+                    self.consume_token(TokenType::Rightbrack, "Expect ']' after index.")?;
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    index: Box::new(index),
+                    bracket,
+                };
+            } else if match_tokens!(self, TokenType::Dot) {
+                let name =
+                    self.consume_token(TokenType::Identifier, "Expect property name after '.'.")?;
+                expr = Expr::Get {
+                    object: Box::new(expr),
+                    name,
+                };
+            } else {
+                break;
+            }
+        }
+
+        Ok(expr)
+    }
+
+    fn finish_call(&mut self, callee: Expr) -> ParseResult<Expr> {
+        let (paren, arguments) = self.arguments()?;
+
+        Ok(Expr::Call {
+            callee: Box::new(callee),
+            paren,
+            arguments,
+        })
+    }
+
+    /// parses the arguments after an opening '(' and returns the closing ')'
+    fn arguments(&mut self) -> ParseResult<(Token, Vec<Expr>)> {
+        let mut arguments = Vec::new();
+
+        if !self.check(TokenType::Rightparen) {
+            loop {
+                arguments.push(self.expression()?);
+
+                if !match_tokens!(self, TokenType::Comma) {
+                    break;
+                }
+            }
         }
 
-        self.primary()
+        let paren = self.consume_token(TokenType::Rightparen, "Expect ')' after arguments.")?;
+
+        Ok((paren, arguments))
     }
 
-    fn primary(&mut self) -> Expr {
+    fn primary(&mut self) -> ParseResult<Expr> {
         if match_tokens!(self, TokenType::False) {
-            return Expr::LiteralFalse;
+            return Ok(Expr::LiteralFalse(self.previous().span));
         }
         if match_tokens!(self, TokenType::True) {
-            return Expr::LiteralTrue;
+            return Ok(Expr::LiteralTrue(self.previous().span));
         }
         if match_tokens!(self, TokenType::Null) {
-            return Expr::LiteralNull;
+            return Ok(Expr::LiteralNull(self.previous().span));
         }
         if match_tokens!(self, TokenType::Int) {
-            let expr = Expr::LiteralInt(self.previous().lexeme.parse().unwrap());
-
-            if match_tokens!(self, TokenType::Power) {
-                let op = self.previous();
-                let right = self.primary();
-                return Expr::Binary {
-                    left: Box::new(expr),
-                    operator: op,
-                    right: Box::new(right),
-                };
-            }
-
-            return expr;
+            let token = self.previous();
+            let value = token
+                .lexeme
+                .parse()
+                .map_err(|_| self.error("E0106", &token, "Invalid int literal."))?;
+            return Ok(Expr::LiteralInt(value, token.span));
         }
         if match_tokens!(self, TokenType::Double) {
-            let expr = Expr::LiteralDouble(self.previous().lexeme.parse().unwrap());
-
-            if match_tokens!(self, TokenType::Power) {
-                let op = self.previous();
-                let right = self.primary();
-                return Expr::Binary {
-                    left: Box::new(expr),
-                    operator: op,
-                    right: Box::new(right),
-                };
+            let token = self.previous();
+            let value = token
+                .lexeme
+                .parse()
+                .map_err(|_| self.error("E0106", &token, "Invalid double literal."))?;
+            return Ok(Expr::LiteralDouble(value, token.span));
+        }
+        if match_tokens!(self, TokenType::String) {
+            let token = self.previous();
+            return Ok(Expr::LiteralString(token.lexeme, token.span));
+        }
+        if match_tokens!(self, TokenType::Char) {
+            let token = self.previous();
+            // the lexer guarantees exactly one character
+            let c = token.lexeme.chars().next().unwrap_or_default();
+            return Ok(Expr::LiteralChar(c, token.span));
+        }
+        // `in` is a keyword, but `in!` is still a builtin
+        if self.check(TokenType::In) && self.check_next(TokenType::Bang) {
+            let name = self.advance();
+            return self.builtin(name);
+        }
+        if match_tokens!(self, TokenType::Identifier) {
+            let name = self.previous();
+
+            if self.check(TokenType::Bang) {
+                return self.builtin(name);
             }
 
-            return expr;
+            return Ok(Expr::Variable(name));
         }
-        if match_tokens!(self, TokenType::String) {
-            return Expr::LiteralString(self.previous().lexeme);
+        if match_tokens!(self, TokenType::This) {
+            return Ok(Expr::This(self.previous()));
+        }
+        if match_tokens!(self, TokenType::New) {
+            let keyword = self.previous();
+            let class =
+                self.consume_token(TokenType::Identifier, "Expect class name after 'new'.")?;
+            self.consume_token(TokenType::Leftparen, "Expect '(' after class name.")?;
+            let (paren, arguments) = self.arguments()?;
+
+            return Ok(Expr::New {
+                keyword,
+                class,
+                paren,
+                arguments,
+            });
+        }
+
+        if match_tokens!(self, TokenType::Leftbrack) {
+            let start = self.previous().span;
+            let mut elements = Vec::new();
+
+            if !self.check(TokenType::Rightbrack) {
+                loop {
+                    elements.push(self.expression()?);
+
+                    if !match_tokens!(self, TokenType::Comma) {
+                        break;
+                    }
+                }
+            }
+
+            let end =
+                self.consume_token(TokenType::Rightbrack, "Expect ']' after array elements.")?;
+            return Ok(Expr::Array(elements, start.to(end.span)));
         }
 
         if match_tokens!(self, TokenType::Leftparen) {
-            let expr = self.expression();
-            self.consume_token(TokenType::Rightparen, "Expect ')' after expression.");
-            return Expr::Grouping(Box::new(expr));
+            let expr = self.expression()?;
+            self.consume_token(TokenType::Rightparen, "Expect ')' after expression.")?;
+            return Ok(Expr::Grouping(Box::new(expr)));
         }
 
-        self.error(self.peek(), "Expected expression.");
+        Err(self.error_at_current("E0102", "Expected expression."))
     }
 
-    fn expression(&mut self) -> Expr {
-        self.equality()
+    /// parses the `!(...)` of a builtin call after its name
+    fn builtin(&mut self, name: Token) -> ParseResult<Expr> {
+        self.consume_token(TokenType::Bang, "Expect '!' after builtin name.")?;
+        self.consume_token(TokenType::Leftparen, "Expect '(' after builtin name.")?;
+        let (paren, arguments) = self.arguments()?;
+
+        Ok(Expr::Builtin {
+            name,
+            paren,
+            arguments,
+        })
     }
 
-    fn consume_token(&mut self, token_type: TokenType, msg: &str) -> Token {
+    pub fn expression(&mut self) -> ParseResult<Expr> {
+        self.assignment()
+    }
+
+    /// the doc comment written in front of the current token
+    fn current_doc(&self) -> Option<String> {
+        self.docs.get(&self.current).cloned()
+    }
+
+    fn consume_token(&mut self, token_type: TokenType, msg: &str) -> ParseResult<Token> {
         if self.check(token_type) {
-            return self.advance();
+            return Ok(self.advance());
         }
 
-        self.error(self.peek(), msg);
+        Err(self.error_at_current("E0101", msg))
     }
 
-    fn error(&self, token: Token, msg: &str) -> ! {
-        reporter::report_error(msg, &self.file_path, token.span, None);
-        panic!("parsing error");
+    fn error(&self, code: &'static str, token: &Token, msg: &str) -> ParseError {
+        ParseError {
+            code,
+            message: msg.to_string(),
+            span: token.span,
+        }
     }
 
-    fn check(&mut self, token_type: TokenType) -> bool {
+    fn error_at_current(&self, code: &'static str, msg: &str) -> ParseError {
+        if !self.is_at_end() {
+            return self.error(code, &self.peek(), msg);
+        }
+
+        // point right behind the last token if the input ended too early
+        let end = self.tokens.last().map_or(0, |t| t.span.end);
+
+        ParseError {
+            code,
+            message: format!("{} Found end of file.", msg),
+            span: TokenSpan { start: end, end },
+        }
+    }
+
+    /// records the error and skips to the next statement boundary,
+    /// always making progress so the caller can't loop forever
+    fn recover(&mut self, err: ParseError, start: usize) {
+        self.errors.push(err);
+        self.synchronize();
+
+        if self.current == start {
+            self.advance();
+        }
+    }
+
+    fn synchronize(&mut self) {
+        while !self.is_at_end() {
+            match self.peek().token_type {
+                TokenType::Fn
+                | TokenType::Class
+                | TokenType::If
+                | TokenType::While
+                | TokenType::For
+                | TokenType::Return
+                | TokenType::Rightcurl => return,
+                _ => {}
+            }
+
+            if self.advance().token_type == TokenType::Semicolon {
+                return;
+            }
+        }
+    }
+
+    fn check(&self, token_type: TokenType) -> bool {
         if self.is_at_end() {
             return false;
         }
@@ -230,6 +909,18 @@ impl Parser {
         self.peek().token_type == token_type
     }
 
+    fn check_next(&self, token_type: TokenType) -> bool {
+        self.check_nth(1, token_type)
+    }
+
+    /// checks the token `n` tokens after the current one
+    fn check_nth(&self, n: usize, token_type: TokenType) -> bool {
+        match self.tokens.get(self.current + n) {
+            Some(token) => token.token_type == token_type,
+            None => false,
+        }
+    }
+
     fn previous(&self) -> Token {
         self.tokens[self.current - 1].clone()
     }
@@ -253,28 +944,25 @@ impl Parser {
 
 #[test]
 fn test_pasic_parsing() {
-    let mut parser = Parser::new(
-        vec![
-            Token {
-                token_type: TokenType::Int,
-                lexeme: String::from("3"),
-                span: TokenSpan { start: 0, end: 1 },
-            },
-            Token {
-                token_type: TokenType::Plus,
-                lexeme: String::from("+"),
-                span: TokenSpan { start: 0, end: 1 },
-            },
-            Token {
-                token_type: TokenType::Int,
-                lexeme: String::from("2"),
-                span: TokenSpan { start: 0, end: 1 },
-            },
-        ],
-        "".to_string(),
-    );
+    let mut parser = Parser::new(vec![
+        Token {
+            token_type: TokenType::Int,
+            lexeme: String::from("3"),
+            span: TokenSpan { start: 0, end: 1 },
+        },
+        Token {
+            token_type: TokenType::Plus,
+            lexeme: String::from("+"),
+            span: TokenSpan { start: 0, end: 1 },
+        },
+        Token {
+            token_type: TokenType::Int,
+            lexeme: String::from("2"),
+            span: TokenSpan { start: 0, end: 1 },
+        },
+    ]);
 
-    let expr = parser.expression();
+    let expr = parser.expression().unwrap();
 
     assert!(matches!(
         expr,
@@ -293,34 +981,224 @@ fn test_pasic_parsing() {
     {
         assert!(operator.token_type == TokenType::Plus);
 
-        assert!(matches!(*left, Expr::LiteralInt(3)));
-        assert!(matches!(*right, Expr::LiteralInt(2)));
+        assert!(matches!(*left, Expr::LiteralInt(3, _)));
+        assert!(matches!(*right, Expr::LiteralInt(2, _)));
     }
 }
 
 #[test]
 fn test_match_tokens() {
-    let mut parser = Parser::new(
-        vec![
-            Token {
-                token_type: TokenType::Int,
-                lexeme: String::from("3"),
-                span: TokenSpan { start: 0, end: 1 },
-            },
-            Token {
-                token_type: TokenType::Plus,
-                lexeme: String::from("+"),
-                span: TokenSpan { start: 0, end: 1 },
-            },
-            Token {
-                token_type: TokenType::Int,
-                lexeme: String::from("2"),
-                span: TokenSpan { start: 0, end: 1 },
-            },
-        ],
-        "".to_string(),
-    );
+    let mut parser = Parser::new(vec![
+        Token {
+            token_type: TokenType::Int,
+            lexeme: String::from("3"),
+            span: TokenSpan { start: 0, end: 1 },
+        },
+        Token {
+            token_type: TokenType::Plus,
+            lexeme: String::from("+"),
+            span: TokenSpan { start: 0, end: 1 },
+        },
+        Token {
+            token_type: TokenType::Int,
+            lexeme: String::from("2"),
+            span: TokenSpan { start: 0, end: 1 },
+        },
+    ]);
 
     assert!(match_tokens!(parser, TokenType::Int));
     assert!(match_tokens!(parser, TokenType::Int, TokenType::Plus));
 }
+
+#[cfg(test)]
+fn parse_source(source: &str) -> Program {
+    let tokens = crate::lexer::Lexer::from_string(source.to_string())
+        .lex_tokens()
+        .0;
+
+    Parser::new(tokens).parse().unwrap()
+}
+
+#[test]
+fn test_class_parsing() {
+    let program = parse_source(
+        "
+        class Test {
+            String str;
+
+            fn Test() {}
+
+            fn compute(int num) > int {
+                return num * 2;
+            }
+        }
+    ",
+    );
+
+    if let Decl::Class(class) = &program.declarations[0] {
+        assert_eq!(class.name.lexeme, "Test");
+        assert_eq!(class.fields.len(), 1);
+        assert!(class.constructor.is_some());
+        assert_eq!(class.methods.len(), 1);
+        assert_eq!(class.methods[0].return_type, Type::Int);
+    } else {
+        panic!("expected class declaration");
+    }
+}
+
+#[test]
+fn test_error_recovery() {
+    let tokens = crate::lexer::Lexer::from_string(String::from(
+        "
+        fn main() {
+            int a = ;
+            int b = 2
+            int c = 3;
+            foo(;
+        }
+
+        fn other( {}
+
+        fn last() {}
+    ",
+    ))
+    .lex_tokens()
+    .0;
+
+    let errors = Parser::new(tokens).parse().unwrap_err();
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+
+    assert_eq!(
+        messages,
+        vec![
+            "Expected expression.",
+            "Expect ';' after variable declaration.",
+            "Expected expression.",
+            "Expect type.",
+        ]
+    );
+}
+
+#[test]
+fn test_unexpected_end_of_file() {
+    let tokens = crate::lexer::Lexer::from_string(String::from("fn main() {"))
+        .lex_tokens()
+        .0;
+
+    let errors = Parser::new(tokens).parse().unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message,
+        "Expect '}' after block. Found end of file."
+    );
+}
+
+#[test]
+fn test_doc_comments() {
+    let program = parse_source(
+        "
+        /// a test class
+        class Test {
+            /// builds it
+            Test() {}
+
+            fn undocumented() {
+                /// ignored inside of a body
+                int a = 1;
+            }
+        }
+
+        /// first line
+        /// second line
+        fn main() {}
+    ",
+    );
+
+    if let Decl::Class(class) = &program.declarations[0] {
+        assert_eq!(class.doc.as_deref(), Some("a test class"));
+        assert_eq!(
+            class.constructor.as_ref().unwrap().doc.as_deref(),
+            Some("builds it")
+        );
+        assert_eq!(class.methods[0].doc, None);
+    } else {
+        panic!("expected class declaration");
+    }
+
+    if let Decl::Function(function) = &program.declarations[1] {
+        assert_eq!(function.doc.as_deref(), Some("first line\nsecond line"));
+    } else {
+        panic!("expected function declaration");
+    }
+}
+
+#[test]
+fn test_logical_precedence() {
+    let tokens = crate::lexer::Lexer::from_string(String::from("a < 1 || b && c == d"))
+        .lex_tokens()
+        .0;
+
+    let expr = Parser::new(tokens).expression().unwrap();
+
+    // `&&` binds tighter than `||`, and both are looser than comparisons
+    if let Expr::Logical {
+        left,
+        operator,
+        right,
+    } = expr
+    {
+        assert_eq!(operator.token_type, TokenType::Or);
+        assert!(matches!(*left, Expr::Binary { .. }));
+        assert!(matches!(
+            *right,
+            Expr::Logical {
+                operator: Token {
+                    token_type: TokenType::And,
+                    ..
+                },
+                ..
+            }
+        ));
+    } else {
+        panic!("expected logical expression");
+    }
+}
+
+#[test]
+fn test_power_precedence() {
+    let tokens = crate::lexer::Lexer::from_string(String::from("-x ** 2 ** 3"))
+        .lex_tokens()
+        .0;
+
+    let expr = Parser::new(tokens).expression().unwrap();
+
+    // -(x ** (2 ** 3))
+    if let Expr::Unary { right, .. } = expr {
+        if let Expr::Binary { left, right, .. } = *right {
+            assert!(matches!(*left, Expr::Variable(_)));
+            assert!(matches!(*right, Expr::Binary { .. }));
+            return;
+        }
+    }
+
+    panic!("wrong precedence for '**'");
+}
+
+#[test]
+fn test_invalid_assignment_target() {
+    let tokens = crate::lexer::Lexer::from_string(String::from(
+        "fn main() { a = 1; a.b += 2; (a + 1) = 3; f() *= 4; }",
+    ))
+    .lex_tokens()
+    .0;
+
+    let errors = Parser::new(tokens).parse().unwrap_err();
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].message, "Invalid assignment target.");
+    assert_eq!(errors[0].code, "E0104");
+    // the whole target is highlighted, not just the operator
+    assert_eq!((errors[0].span.start, errors[0].span.end), (30, 35));
+    assert_eq!((errors[1].span.start, errors[1].span.end), (42, 45));
+}
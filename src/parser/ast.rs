@@ -0,0 +1,272 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::lexer::token::{Token, TokenSpan};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int,
+    Double,
+    Bool,
+    Char,
+    String,
+    Class(String),
+    Array(Box<Type>),
+    Null,
+    Void,
+}
+
+impl Type {
+    /// the type both types can be stored in, used for the elements of array literals
+    pub fn common(&self, other: &Type) -> Option<Type> {
+        match (self, other) {
+            _ if self == other => Some(self.clone()),
+            (Type::Int, Type::Double) | (Type::Double, Type::Int) => Some(Type::Double),
+            (Type::Null, Type::String | Type::Class(_) | Type::Array(_)) => Some(other.clone()),
+            (Type::String | Type::Class(_) | Type::Array(_), Type::Null) => Some(self.clone()),
+            (Type::Array(element), Type::Array(other_element)) => {
+                if element.fits_elements(other_element) {
+                    Some(self.clone())
+                } else if other_element.fits_elements(element) {
+                    Some(other.clone())
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// true if an array with elements of type `source` can be used as an array
+    /// of this element type. a literal only lacks the element type if it is
+    /// empty or all elements are `null`, which fits every array, String or
+    /// class element. other elements are never converted, as the array is
+    /// shared by reference, so an int[] doesn't fit a double[]
+    pub fn fits_elements(&self, source: &Type) -> bool {
+        match (self, source) {
+            (_, Type::Void) => true,
+            (Type::String | Type::Class(_) | Type::Array(_), Type::Null) => true,
+            (Type::Array(element), Type::Array(source)) => element.fits_elements(source),
+            _ => self == source,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Double => write!(f, "double"),
+            Type::Bool => write!(f, "bool"),
+            Type::Char => write!(f, "char"),
+            Type::String => write!(f, "String"),
+            Type::Class(name) => write!(f, "{}", name),
+            Type::Array(element) => write!(f, "{}[]", element),
+            Type::Null => write!(f, "null"),
+            Type::Void => write!(f, "void"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Expr {
+    Binary {
+        left: Box<Expr>,
+        operator: Token,
+        right: Box<Expr>,
+    },
+    Unary {
+        operator: Token,
+        right: Box<Expr>,
+    },
+    // `&&` and `||`, which don't always evaluate the right side
+    Logical {
+        left: Box<Expr>,
+        operator: Token,
+        right: Box<Expr>,
+    },
+    Grouping(Box<Expr>),
+    Variable(Token),
+    Call {
+        callee: Box<Expr>,
+        paren: Token, // closing ')'
+        arguments: Vec<Expr>,
+    },
+    Get {
+        object: Box<Expr>,
+        name: Token,
+    },
+    Assign {
+        name: Token,
+        operator: Token, // `=` or a compound operator like `+=`
+        value: Box<Expr>,
+    },
+    Set {
+        object: Box<Expr>,
+        name: Token,
+        operator: Token,
+        value: Box<Expr>,
+    },
+    Index {
+        object: Box<Expr>,
+        index: Box<Expr>,
+        bracket: Token, // closing ']'
+    },
+    SetIndex {
+        object: Box<Expr>,
+        index: Box<Expr>,
+        operator: Token,
+        value: Box<Expr>,
+    },
+    Array(Vec<Expr>, TokenSpan),
+    // a call of a builtin like `out!(...)`
+    Builtin {
+        name: Token,
+        paren: Token, // closing ')'
+        arguments: Vec<Expr>,
+    },
+    This(Token),
+    New {
+        keyword: Token,
+        class: Token,
+        paren: Token, // closing ')'
+        arguments: Vec<Expr>,
+    },
+
+    // TODO: find better way of doing this (maybe seperate enum???)
+    LiteralTrue(TokenSpan),
+    LiteralFalse(TokenSpan),
+    LiteralNull(TokenSpan),
+    LiteralInt(i64, TokenSpan),
+    LiteralDouble(f64, TokenSpan),
+    LiteralString(String, TokenSpan),
+    LiteralChar(char, TokenSpan),
+}
+
+impl Expr {
+    pub fn span(&self) -> TokenSpan {
+        match self {
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                left.span().to(right.span())
+            }
+            Expr::Unary { operator, right } => operator.span.to(right.span()),
+            Expr::Grouping(expr) => expr.span(),
+            Expr::Variable(name) => name.span,
+            Expr::Call { callee, paren, .. } => callee.span().to(paren.span),
+            Expr::Get { object, name } => object.span().to(name.span),
+            Expr::Assign { name, value, .. } => name.span.to(value.span()),
+            Expr::Set { object, value, .. } | Expr::SetIndex { object, value, .. } => {
+                object.span().to(value.span())
+            }
+            Expr::Index {
+                object, bracket, ..
+            } => object.span().to(bracket.span),
+            Expr::Builtin { name, paren, .. } => name.span.to(paren.span),
+            Expr::This(keyword) => keyword.span,
+            Expr::New { keyword, paren, .. } => keyword.span.to(paren.span),
+            Expr::LiteralTrue(span)
+            | Expr::LiteralFalse(span)
+            | Expr::LiteralNull(span)
+            | Expr::LiteralInt(_, span)
+            | Expr::LiteralDouble(_, span)
+            | Expr::LiteralString(_, span)
+            | Expr::LiteralChar(_, span)
+            | Expr::Array(_, span) => *span,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct VarDecl {
+    pub ty: Type,
+    pub name: Token,
+    pub initializer: Option<Expr>,
+}
+
+#[derive(Debug)]
+pub enum Stmt {
+    Expression(Expr),
+    Var(VarDecl),
+    Block(Vec<Stmt>),
+    If {
+        condition: Expr,
+        then_branch: Box<Stmt>,
+        else_branch: Option<Box<Stmt>>,
+    },
+    While {
+        condition: Expr,
+        body: Box<Stmt>,
+    },
+    For {
+        initializer: Option<Box<Stmt>>,
+        condition: Option<Expr>,
+        increment: Option<Expr>,
+        body: Box<Stmt>,
+    },
+    // `for x in array`
+    ForIn {
+        name: Token,
+        iterable: Expr,
+        body: Box<Stmt>,
+    },
+    Return {
+        keyword: Token,
+        value: Option<Expr>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Param {
+    pub ty: Type,
+    pub name: Token,
+}
+
+#[derive(Debug)]
+pub struct FunctionDecl {
+    // not used by the interpreter, only kept for tooling
+    pub doc: Option<String>,
+    pub name: Token,
+    pub params: Vec<Param>,
+    pub return_type: Type,
+    pub body: Vec<Stmt>,
+}
+
+#[derive(Debug)]
+pub struct ClassDecl {
+    pub doc: Option<String>,
+    pub name: Token,
+    pub fields: Vec<VarDecl>,
+    pub constructor: Option<Rc<FunctionDecl>>,
+    pub methods: Vec<Rc<FunctionDecl>>,
+}
+
+impl ClassDecl {
+    pub fn find_method(&self, name: &str) -> Option<&Rc<FunctionDecl>> {
+        self.methods.iter().find(|m| m.name.lexeme == name)
+    }
+
+    pub fn find_field(&self, name: &str) -> Option<&VarDecl> {
+        self.fields.iter().find(|f| f.name.lexeme == name)
+    }
+}
+
+#[derive(Debug)]
+pub enum Decl {
+    Function(Rc<FunctionDecl>),
+    Class(Rc<ClassDecl>),
+    Var(Box<VarDecl>),
+}
+
+#[derive(Debug)]
+pub struct Program {
+    pub declarations: Vec<Decl>,
+}
+
+/// a piece of input of the REPL, which isn't restricted to declarations
+#[derive(Debug)]
+pub enum Entry {
+    Decl(Decl),
+    Stmt(Stmt),
+    // an expression without ';' at the end of the input, its value is printed
+    Expr(Expr),
+}
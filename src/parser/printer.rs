@@ -0,0 +1,319 @@
+use super::ast::{ClassDecl, Decl, Expr, FunctionDecl, Program, Stmt, VarDecl};
+
+/// renders the syntax tree as an indented outline, one node per line
+pub fn print(program: &Program) -> String {
+    let mut printer = Printer::default();
+
+    for decl in &program.declarations {
+        printer.decl(decl);
+    }
+
+    printer.output
+}
+
+#[derive(Default)]
+struct Printer {
+    output: String,
+    indent: usize,
+}
+
+impl Printer {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.output.push_str("  ");
+        }
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+
+    /// prints a node with its children indented below it
+    fn node(&mut self, text: &str, children: impl FnOnce(&mut Printer)) {
+        self.line(text);
+        self.indent += 1;
+        children(self);
+        self.indent -= 1;
+    }
+
+    fn doc(&mut self, doc: &Option<String>) {
+        if let Some(doc) = doc {
+            for line in doc.lines() {
+                self.line(&format!("/// {}", line));
+            }
+        }
+    }
+
+    fn decl(&mut self, decl: &Decl) {
+        match decl {
+            Decl::Function(function) => self.function("fn", function),
+            Decl::Class(class) => self.class(class),
+            Decl::Var(var) => self.var(var),
+        }
+    }
+
+    fn function(&mut self, kind: &str, function: &FunctionDecl) {
+        let params: Vec<String> = function
+            .params
+            .iter()
+            .map(|p| format!("{} {}", p.ty, p.name.lexeme))
+            .collect();
+
+        self.doc(&function.doc);
+        self.node(
+            &format!(
+                "{} {}({}) > {}",
+                kind,
+                function.name.lexeme,
+                params.join(", "),
+                function.return_type
+            ),
+            |p| {
+                for stmt in &function.body {
+                    p.stmt(stmt);
+                }
+            },
+        );
+    }
+
+    fn class(&mut self, class: &ClassDecl) {
+        self.doc(&class.doc);
+        self.node(&format!("class {}", class.name.lexeme), |p| {
+            for field in &class.fields {
+                p.var(field);
+            }
+            if let Some(constructor) = &class.constructor {
+                p.function("constructor", constructor);
+            }
+            for method in &class.methods {
+                p.function("fn", method);
+            }
+        });
+    }
+
+    fn var(&mut self, var: &VarDecl) {
+        self.node(&format!("Var {} {}", var.ty, var.name.lexeme), |p| {
+            if let Some(initializer) = &var.initializer {
+                p.expr(initializer);
+            }
+        });
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression(expr) => self.node("Expression", |p| p.expr(expr)),
+            Stmt::Var(var) => self.var(var),
+            Stmt::Block(statements) => self.node("Block", |p| {
+                for stmt in statements {
+                    p.stmt(stmt);
+                }
+            }),
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => self.node("If", |p| {
+                p.node("Condition", |p| p.expr(condition));
+                p.node("Then", |p| p.stmt(then_branch));
+                if let Some(else_branch) = else_branch {
+                    p.node("Else", |p| p.stmt(else_branch));
+                }
+            }),
+            Stmt::While { condition, body } => self.node("While", |p| {
+                p.node("Condition", |p| p.expr(condition));
+                p.node("Body", |p| p.stmt(body));
+            }),
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+            } => self.node("For", |p| {
+                if let Some(initializer) = initializer {
+                    p.node("Initializer", |p| p.stmt(initializer));
+                }
+                if let Some(condition) = condition {
+                    p.node("Condition", |p| p.expr(condition));
+                }
+                if let Some(increment) = increment {
+                    p.node("Increment", |p| p.expr(increment));
+                }
+                p.node("Body", |p| p.stmt(body));
+            }),
+            Stmt::ForIn {
+                name,
+                iterable,
+                body,
+            } => self.node(&format!("ForIn {}", name.lexeme), |p| {
+                p.node("Iterable", |p| p.expr(iterable));
+                p.node("Body", |p| p.stmt(body));
+            }),
+            Stmt::Return { value, .. } => self.node("Return", |p| {
+                if let Some(value) = value {
+                    p.expr(value);
+                }
+            }),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => self.node(&format!("Binary {}", operator.lexeme), |p| {
+                p.expr(left);
+                p.expr(right);
+            }),
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => self.node(&format!("Logical {}", operator.lexeme), |p| {
+                p.expr(left);
+                p.expr(right);
+            }),
+            Expr::Unary { operator, right } => {
+                self.node(&format!("Unary {}", operator.lexeme), |p| p.expr(right))
+            }
+            Expr::Grouping(expr) => self.node("Grouping", |p| p.expr(expr)),
+            Expr::Variable(name) => self.line(&format!("Variable {}", name.lexeme)),
+            Expr::Assign {
+                name,
+                operator,
+                value,
+            } => self.node(
+                &format!("Assign {} {}", name.lexeme, operator.lexeme),
+                |p| p.expr(value),
+            ),
+            Expr::Call {
+                callee, arguments, ..
+            } => self.node("Call", |p| {
+                p.expr(callee);
+                p.arguments(arguments);
+            }),
+            Expr::Get { object, name } => {
+                self.node(&format!("Get {}", name.lexeme), |p| p.expr(object))
+            }
+            Expr::Set {
+                object,
+                name,
+                operator,
+                value,
+            } => self.node(&format!("Set {} {}", name.lexeme, operator.lexeme), |p| {
+                p.expr(object);
+                p.expr(value);
+            }),
+            Expr::Index { object, index, .. } => self.node("Index", |p| {
+                p.expr(object);
+                p.expr(index);
+            }),
+            Expr::SetIndex {
+                object,
+                index,
+                operator,
+                value,
+            } => self.node(&format!("SetIndex {}", operator.lexeme), |p| {
+                p.expr(object);
+                p.expr(index);
+                p.expr(value);
+            }),
+            Expr::Array(elements, _) => self.node("Array", |p| {
+                for element in elements {
+                    p.expr(element);
+                }
+            }),
+            Expr::Builtin {
+                name, arguments, ..
+            } => self.node(&format!("Builtin {}!", name.lexeme), |p| {
+                for argument in arguments {
+                    p.expr(argument);
+                }
+            }),
+            Expr::This(_) => self.line("This"),
+            Expr::New {
+                class, arguments, ..
+            } => self.node(&format!("New {}", class.lexeme), |p| {
+                for argument in arguments {
+                    p.expr(argument);
+                }
+            }),
+            Expr::LiteralTrue(_) => self.line("Bool true"),
+            Expr::LiteralFalse(_) => self.line("Bool false"),
+            Expr::LiteralNull(_) => self.line("Null"),
+            Expr::LiteralInt(i, _) => self.line(&format!("Int {}", i)),
+            Expr::LiteralDouble(d, _) => self.line(&format!("Double {:?}", d)),
+            Expr::LiteralString(s, _) => self.line(&format!("String {:?}", s)),
+            Expr::LiteralChar(c, _) => self.line(&format!("Char {:?}", c)),
+        }
+    }
+
+    fn arguments(&mut self, arguments: &[Expr]) {
+        if !arguments.is_empty() {
+            self.node("Arguments", |p| {
+                for argument in arguments {
+                    p.expr(argument);
+                }
+            });
+        }
+    }
+}
+
+#[test]
+fn test_print_program() {
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    let source = "
+        /// counts things
+        class Counter {
+            int count = 1;
+
+            Counter(int start) {
+                this.count += start;
+            }
+        }
+
+        fn main() > int {
+            for x in [1, 2.5] {
+                out!(x, \"a\");
+            }
+            return -new Counter(2).count * 3;
+        }
+    ";
+
+    let tokens = Lexer::from_string(source.to_string()).lex_tokens().0;
+    let program = Parser::new(tokens).parse().unwrap();
+
+    let expected = "\
+/// counts things
+class Counter
+  Var int count
+    Int 1
+  constructor Counter(int start) > void
+    Expression
+      Set count +=
+        This
+        Variable start
+fn main() > int
+  ForIn x
+    Iterable
+      Array
+        Int 1
+        Double 2.5
+    Body
+      Block
+        Expression
+          Builtin out!
+            Variable x
+            String \"a\"
+  Return
+    Binary *
+      Unary -
+        Get count
+          New Counter
+            Int 2
+      Int 3
+";
+
+    assert_eq!(print(&program), expected);
+}
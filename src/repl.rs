@@ -0,0 +1,199 @@
+use std::io::{self, BufRead, Write};
+
+use crate::checker::Checker;
+use crate::interpreter::value::Value;
+use crate::interpreter::Interpreter;
+use crate::lexer::token::{TokenSpan, TokenType};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::reporter::source_map::FileId;
+use crate::reporter::{ErrorFormat, Reporter};
+
+/// reads inputs from stdin and runs them until the input ends
+pub fn run(error_format: ErrorFormat) {
+    let mut repl = Repl::new(Reporter::new(error_format));
+    let stdin = io::stdin();
+    let mut input = String::new();
+
+    loop {
+        print!("{}", if input.is_empty() { "> " } else { "... " });
+        io::stdout().flush().unwrap();
+
+        match stdin.lock().read_line(&mut input) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+
+        // unbalanced braces continue on the next line
+        if !is_complete(&input) {
+            continue;
+        }
+
+        if let Some(result) = repl.eval(&input) {
+            println!("{}", result);
+        }
+        input.clear();
+    }
+
+    println!();
+}
+
+/// true unless the input has more opening than closing brackets
+fn is_complete(input: &str) -> bool {
+    let tokens = Lexer::from_string(input.to_string()).lex_tokens().0;
+
+    let depth: i32 = tokens
+        .iter()
+        .map(|token| match token.token_type {
+            TokenType::Leftparen | TokenType::Leftcurl | TokenType::Leftbrack => 1,
+            TokenType::Rightparen | TokenType::Rightcurl | TokenType::Rightbrack => -1,
+            _ => 0,
+        })
+        .sum();
+
+    depth <= 0
+}
+
+/// keeps the declarations of every input for the next ones
+pub struct Repl {
+    checker: Checker,
+    interpreter: Interpreter,
+    reporter: Reporter,
+    // every input so far, spans point into it so earlier functions are reported correctly
+    file: FileId,
+}
+
+impl Repl {
+    pub fn new(reporter: Reporter) -> Self {
+        Repl::with_interpreter(reporter, Interpreter::new())
+    }
+
+    fn with_interpreter(mut reporter: Reporter, interpreter: Interpreter) -> Self {
+        let file = reporter.sources.add("<repl>", String::new());
+
+        Repl {
+            checker: Checker::new(),
+            interpreter,
+            reporter,
+            file,
+        }
+    }
+
+    /// runs a complete input and returns the value of its final expression,
+    /// errors are reported against the REPL buffer
+    pub fn eval(&mut self, input: &str) -> Option<String> {
+        let offset = self.reporter.sources.append(self.file, input);
+
+        let (mut tokens, mut diagnostics) = Lexer::from_string(input.to_string()).lex_tokens();
+        for token in &mut tokens {
+            token.span.start += offset;
+            token.span.end += offset;
+        }
+        for diagnostic in &mut diagnostics {
+            diagnostic.span.start += offset;
+            diagnostic.span.end += offset;
+            self.reporter.report(diagnostic, self.file);
+        }
+
+        if diagnostics.iter().any(|d| d.is_error()) {
+            return None;
+        }
+
+        let entries = match Parser::new(tokens).parse_entries() {
+            Ok(entries) => entries,
+            Err(errors) => {
+                for error in errors {
+                    self.report(error.code, &error.message, error.span);
+                }
+                return None;
+            }
+        };
+
+        // a rejected or failing input must not leave its declarations behind,
+        // the checker only keeps them once they ran without an error
+        let mut checker = self.checker.clone();
+        if let Err(errors) = checker.check_entries(&entries) {
+            for error in errors {
+                self.report(error.code, &error.message, error.span);
+            }
+            return None;
+        }
+
+        let result = match self.interpreter.run_entries(&entries) {
+            Ok(result) => result,
+            Err(err) => {
+                self.reporter.report(&err.diagnostic(), self.file);
+                return None;
+            }
+        };
+        self.checker = checker;
+
+        match result {
+            Some(Value::Null) | None => None,
+            Some(value) => Some(value.display(self.interpreter.heap()).to_string()),
+        }
+    }
+
+    fn report(&self, code: &'static str, message: &str, span: TokenSpan) {
+        self.reporter
+            .report_error(code, message, self.file, span, None);
+    }
+}
+
+#[test]
+fn test_multi_line_input() {
+    assert!(is_complete("1 + 2"));
+    assert!(!is_complete("fn main() {\n"));
+    assert!(!is_complete("int[] a = [1, (2"));
+    assert!(is_complete("fn main() {\n out!(\"{\");\n}\n"));
+}
+
+#[test]
+fn test_repl_keeps_declarations() {
+    use crate::interpreter::SharedBuffer;
+
+    let output = SharedBuffer::default();
+    let mut repl = Repl::with_interpreter(
+        Reporter::default(),
+        Interpreter::with_output(Box::new(output.clone())),
+    );
+
+    assert_eq!(repl.eval("1 + 2 * 3\n"), Some("7".to_string()));
+    assert_eq!(repl.eval("int x = 2;\n"), None);
+    assert_eq!(
+        repl.eval("fn double(int n) > int {\n    return n * 2;\n}\n"),
+        None
+    );
+    assert_eq!(repl.eval("double(x) + 1\n"), Some("5".to_string()));
+    assert_eq!(repl.eval("x += 1; if (x > 2) { out!(\"big\"); }\n"), None);
+    assert_eq!(repl.eval("class P { int v = 4; }\n"), None);
+    assert_eq!(repl.eval("[new P().v, x]\n"), Some("[4, 3]".to_string()));
+
+    // locals of statements don't outlive the input
+    assert_eq!(repl.eval("{ int y = 1; }\n"), None);
+    assert_eq!(repl.eval("y\n"), None);
+
+    // the failed declaration isn't kept, so it can be fixed
+    assert_eq!(repl.eval("fn f() > int { return true; }\n"), None);
+    assert_eq!(repl.eval("fn f() > int { return 1; }\n"), None);
+    assert_eq!(repl.eval("f()\n"), Some("1".to_string()));
+    assert_eq!(repl.eval("1 / 0\n"), None);
+    assert_eq!(repl.eval("f() + x\n"), Some("4".to_string()));
+
+    assert_eq!(String::from_utf8_lossy(&output.0.borrow()), "big\n");
+}
+
+#[test]
+fn test_repl_forgets_failed_globals() {
+    let mut repl = Repl::new(Reporter::default());
+
+    // the initializer fails, so `x` is neither usable nor taken
+    assert_eq!(repl.eval("int x = 1 / 0;\n"), None);
+    assert_eq!(repl.eval("x\n"), None);
+    assert_eq!(repl.eval("int x = 2;\n"), None);
+    assert_eq!(repl.eval("x\n"), Some("2".to_string()));
+
+    assert_eq!(repl.eval("int y = 1; int z = y / 0;\n"), None);
+    assert_eq!(repl.eval("int y = 3; int z = y;\n"), None);
+    assert_eq!(repl.eval("y + z\n"), Some("6".to_string()));
+}
@@ -0,0 +1,594 @@
+/// long-form explanations of the diagnostic codes, shown by `rawk --explain`.
+/// codes are never reused, E00xx come from the lexer, E01xx from the parser,
+/// E02xx from the checker, E03xx only happen at runtime and Wxxxx are lints
+const EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        "E0001",
+        "A character was found which can't start any token.
+
+Erroneous example:
+
+    int a = 5 $ 3;
+
+Only the characters of operators, literals, names and comments may appear
+outside of String and char literals.",
+    ),
+    (
+        "E0002",
+        "A String literal isn't closed before the end of the file.
+
+Erroneous example:
+
+    String s = \"hello;
+
+Close the literal with a second `\"`. A `\"` inside of the String has to be
+escaped as `\\\"`.",
+    ),
+    (
+        "E0003",
+        "A String or char literal contains an unknown escape sequence.
+
+Erroneous example:
+
+    String path = \"C:\\temp\\q\";
+
+The supported escape sequences are `\\n`, `\\t`, `\\r`, `\\0`, `\\\\`, `\\\"`,
+`\\'` and `\\u{...}`. Write `\\\\` for a backslash.",
+    ),
+    (
+        "E0004",
+        "A unicode escape doesn't name a valid character.
+
+Erroneous example:
+
+    char c = '\\u{110000}';
+
+A unicode escape takes 1 to 6 hex digits in braces, like `\\u{1F600}`, and the
+value has to be a unicode scalar value.",
+    ),
+    (
+        "E0005",
+        "A char literal isn't closed.
+
+Erroneous example:
+
+    char c = 'a;
+
+Close the literal with a second `'`.",
+    ),
+    (
+        "E0006",
+        "A char literal doesn't contain a character.
+
+Erroneous example:
+
+    char c = '';
+
+A char always holds exactly one character. Use an empty String `\"\"` for an
+empty text.",
+    ),
+    (
+        "E0007",
+        "A char literal contains more than one character.
+
+Erroneous example:
+
+    char c = 'ab';
+
+A char holds exactly one character, texts are written with double quotes:
+
+    String s = \"ab\";",
+    ),
+    (
+        "E0008",
+        "A block comment isn't closed before the end of the file.
+
+Erroneous example:
+
+    /* outer /* inner */
+    fn main() {}
+
+Block comments can be nested, so every `/*` needs its own `*/`.",
+    ),
+    (
+        "E0009",
+        "An integer literal doesn't fit into an int, which is a signed 64-bit
+integer.
+
+Erroneous example:
+
+    int big = 9223372036854775808;
+
+Use a double for larger numbers:
+
+    double big = 9223372036854775808.0;",
+    ),
+    (
+        "E0010",
+        "An integer literal contains a digit which isn't allowed by its base.
+
+Erroneous example:
+
+    int mask = 0b102;
+
+Binary literals (`0b`) only take 0 and 1, octal literals (`0o`) 0 to 7 and
+hexadecimal literals (`0x`) 0 to 9 and a to f.",
+    ),
+    (
+        "E0011",
+        "An integer prefix isn't followed by any digits.
+
+Erroneous example:
+
+    int a = 0x;
+
+Write at least one digit after `0x`, `0o` or `0b`, like `0x0`.",
+    ),
+    (
+        "E0012",
+        "A double literal is too large to be represented by a 64-bit float.
+
+Erroneous example:
+
+    double d = 1e400;
+
+The largest double is about 1.8e308.",
+    ),
+    (
+        "E0101",
+        "The parser expected a specific token, like a `;` or a closing bracket,
+but found something else.
+
+Erroneous example:
+
+    fn main() {
+        int a = 5
+    }
+
+The message names the missing token, in this case the `;` after the variable
+declaration.",
+    ),
+    (
+        "E0102",
+        "An expression was expected, but the token can't start one.
+
+Erroneous example:
+
+    int a = * 2;
+
+Expressions start with a literal, a name, a unary operator, `(`, `[`, `new`
+or `this`.",
+    ),
+    (
+        "E0103",
+        "A declaration was expected at the top level of the file or in a class
+body.
+
+Erroneous example:
+
+    out!(\"hi\");
+
+    fn main() {}
+
+Only functions, classes and variables can be declared at the top level,
+statements belong into a function. A class body contains fields, one
+constructor and methods.",
+    ),
+    (
+        "E0104",
+        "The left side of an assignment isn't something which can be assigned.
+
+Erroneous example:
+
+    fn main() {
+        1 = 2;
+    }
+
+Only variables, fields like `a.b` and array elements like `a[0]` can be
+assigned.",
+    ),
+    (
+        "E0105",
+        "A constructor is declared wrongly.
+
+Erroneous example:
+
+    class Point {
+        Point() > int {}
+    }
+
+A class has at most one constructor, which is named like the class and has no
+return type.",
+    ),
+    (
+        "E0106",
+        "A number literal can't be converted to its value.
+
+Erroneous example:
+
+    int a = 99999999999999999999;
+
+Usually E0009 or E0012 is reported for such a literal first.",
+    ),
+    (
+        "E0201",
+        "A name is declared twice.
+
+Erroneous example:
+
+    fn main() {
+        int a = 1;
+        int a = 2;
+    }
+
+Functions and classes need unique names, as do the fields and methods of a
+class and the variables of a scope. A variable of an inner block may use the
+name of an outer one.",
+    ),
+    (
+        "E0202",
+        "A variable or function is used, but isn't declared.
+
+Erroneous example:
+
+    fn main() {
+        out!(count);
+    }
+
+Declare the variable before using it, or check the name for typos. Variables
+are only visible inside of the block which declares them.",
+    ),
+    (
+        "E0203",
+        "A type or class name doesn't refer to a class.
+
+Erroneous example:
+
+    fn main() {
+        Pont p = new Pont();
+    }
+
+Types are either one of the builtin types (`int`, `double`, `bool`, `char`,
+`String`), an array of a type, or a class declared in the program.",
+    ),
+    (
+        "E0204",
+        "A field or method is accessed which the class doesn't have.
+
+Erroneous example:
+
+    class Point {
+        int x;
+    }
+
+    fn main() {
+        Point p = new Point();
+        out!(p.y);
+    }",
+    ),
+    (
+        "E0205",
+        "A builtin is called which doesn't exist.
+
+Erroneous example:
+
+    fn main() {
+        print!(\"hi\");
+    }
+
+The builtins are `out!`, `in!` and `len!`.",
+    ),
+    (
+        "E0206",
+        "A value has a different type than expected.
+
+Erroneous example:
+
+    fn main() {
+        int a = true;
+    }
+
+This covers assignments, arguments, return values, conditions, which have to
+be `bool`, and indices, which have to be `int`. An int is converted to a
+double where a double is expected, but not the other way around.
+
+At runtime this is mostly reported for `null`, like accessing a field of an
+object which is `null`.",
+    ),
+    (
+        "E0207",
+        "An operator is applied to types it doesn't support.
+
+Erroneous example:
+
+    fn main() {
+        bool b = true + 1;
+    }
+
+Arithmetic works on ints and doubles, `+` also concatenates Strings,
+`&&`, `||` and `!` need bools. Comparisons are type safe, so only values of
+compatible types can be compared.",
+    ),
+    (
+        "E0208",
+        "A return statement doesn't fit the function it is in.
+
+Erroneous example:
+
+    fn log(String s) {
+        return s;
+    }
+
+A function without return type can't return a value and a function with a
+return type has to return one. `return` can only be used inside of a
+function.",
+    ),
+    (
+        "E0209",
+        "A function with a return type can end without returning a value.
+
+Erroneous example:
+
+    fn sign(int n) > int {
+        if (n < 0) {
+            return -1;
+        }
+    }
+
+Every path through the function has to end in a return statement:
+
+    fn sign(int n) > int {
+        if (n < 0) {
+            return -1;
+        }
+        return 1;
+    }
+
+A `while (true)` or `for (;;)` loop counts as a path which returns, as it can
+only be left by a return statement.",
+    ),
+    (
+        "E0210",
+        "A variable, field or parameter is declared with a type which can't hold
+a value.
+
+Erroneous example:
+
+    fn main() {
+        void v;
+    }
+
+`void` and `null` aren't types of values. Any String, class or array variable
+can hold `null`.",
+    ),
+    (
+        "E0211",
+        "`this` is used outside of a class.
+
+Erroneous example:
+
+    fn main() {
+        out!(this);
+    }
+
+`this` refers to the object a method is called on, so it can only be used in
+methods, constructors and field initializers.",
+    ),
+    (
+        "E0212",
+        "A function, method, constructor or builtin is called with the wrong
+number of arguments.
+
+Erroneous example:
+
+    fn add(int a, int b) > int {
+        return a + b;
+    }
+
+    fn main() {
+        out!(add(1));
+    }",
+    ),
+    (
+        "E0213",
+        "Something is called which isn't a function.
+
+Erroneous example:
+
+    fn main() {
+        int a = 1;
+        a();
+    }
+
+Only functions and methods can be called.",
+    ),
+    (
+        "E0301",
+        "An int was divided by zero, with `/` or `%`.
+
+Erroneous example:
+
+    fn main() {
+        int zero = 0;
+        out!(1 / zero);
+    }
+
+Check the divisor before dividing. Dividing a double by zero results in
+infinity or NaN instead.",
+    ),
+    (
+        "E0302",
+        "The result of an int operation doesn't fit into a signed 64-bit
+integer.
+
+Erroneous example:
+
+    fn main() {
+        int a = 9223372036854775807;
+        a += 1;
+    }
+
+Use a double if the values can get this large.",
+    ),
+    (
+        "E0303",
+        "An int was raised to a negative power, whose result isn't an int.
+
+Erroneous example:
+
+    fn main() {
+        int e = -1;
+        out!(2 ** e);
+    }
+
+Use a double as base to get a fractional result, like `2.0 ** e`.",
+    ),
+    (
+        "E0304",
+        "An array was accessed at an index which it doesn't have.
+
+Erroneous example:
+
+    fn main() {
+        int[] a = [1, 2, 3];
+        out!(a[3]);
+    }
+
+The indices of an array go from 0 to its length minus one, `len!(a)` returns
+the length.",
+    ),
+    (
+        "E0305",
+        "Too many calls were nested, usually because of a recursion which never
+ends.
+
+Erroneous example:
+
+    fn forever(int n) > int {
+        return forever(n + 1);
+    }
+
+Make sure every recursion has a case which doesn't call the function again.",
+    ),
+    (
+        "E0306",
+        "The program has no `main` function, where it starts.
+
+Erroneous example:
+
+    fn start() {
+        out!(\"hi\");
+    }
+
+Declare a `fn main()`. If it returns an int, the value becomes the exit code
+of the program.",
+    ),
+    (
+        "W0001",
+        "A variable or parameter is declared, but its value is never read.
+(unused-variable)
+
+Example:
+
+    fn main() {
+        int unused = 1;
+        unused = 2;
+    }
+
+Remove the variable, or start its name with `_` if it is unused on
+purpose.",
+    ),
+    (
+        "W0002",
+        "A function is never called. (unused-function)
+
+Example:
+
+    fn helper() {}
+
+    fn main() {}
+
+`main` is never reported, it is called when the program starts.",
+    ),
+    (
+        "W0003",
+        "A statement can never run, because every path before it already
+returned. (unreachable-code)
+
+Example:
+
+    fn main() {
+        return;
+        out!(\"never printed\");
+    }",
+    ),
+    (
+        "W0004",
+        "A variable has the same name as a variable of an enclosing scope or a
+global, which can't be used while it is shadowed. (shadowing)
+
+Example:
+
+    int count = 0;
+
+    fn main() {
+        int count = 1;
+        out!(count);
+    }",
+    ),
+    (
+        "W0005",
+        "The condition of an `if` or loop is always true or always false, so a
+branch is dead. (constant-condition)
+
+Example:
+
+    fn main() {
+        if (false) {
+            out!(\"never printed\");
+        }
+    }
+
+`while (true)` isn't reported, as it is how an endless loop is written.",
+    ),
+];
+
+/// the explanation of a code like `E0202`
+pub fn explain(code: &str) -> Option<&'static str> {
+    let code = code.to_ascii_uppercase();
+
+    EXPLANATIONS
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, explanation)| *explanation)
+}
+
+#[test]
+fn test_every_code_is_explained() {
+    let sources = [
+        include_str!("../lexer.rs"),
+        include_str!("../parser.rs"),
+        include_str!("../checker.rs"),
+        include_str!("../interpreter.rs"),
+        include_str!("../vm.rs"),
+        include_str!("../vm/compiler.rs"),
+        include_str!("../linter.rs"),
+    ];
+
+    let mut count = 0;
+    for source in sources {
+        for (i, _) in source
+            .match_indices("\"E0")
+            .chain(source.match_indices("\"W0"))
+        {
+            let code = &source[i + 1..i + 6];
+            assert!(explain(code).is_some(), "{} has no explanation", code);
+            count += 1;
+        }
+    }
+
+    assert!(count > 100);
+    assert_eq!(explain("e0202"), explain("E0202"));
+    assert!(explain("E0012").is_some());
+    assert_eq!(explain("E9999"), None);
+}
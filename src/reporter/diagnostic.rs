@@ -0,0 +1,64 @@
+use crate::lexer::token::TokenSpan;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// a call which was running when a runtime error happened
+#[derive(Debug, Clone)]
+pub struct TraceFrame {
+    pub function: String,
+    /// where the function was called
+    pub span: TokenSpan,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub span: TokenSpan,
+    pub label: Option<String>,
+    /// the calls of a runtime error, innermost first
+    pub trace: Vec<TraceFrame>,
+}
+
+impl Diagnostic {
+    pub fn error(code: &'static str, message: String, span: TokenSpan) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            code,
+            message,
+            span,
+            label: None,
+            trace: Vec::new(),
+        }
+    }
+
+    pub fn warning(code: &'static str, message: String, span: TokenSpan) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            code,
+            message,
+            span,
+            label: None,
+            trace: Vec::new(),
+        }
+    }
+
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn with_trace(mut self, trace: Vec<TraceFrame>) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
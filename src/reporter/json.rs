@@ -0,0 +1,153 @@
+use std::fmt::Write;
+
+use super::diagnostic::{Diagnostic, Severity};
+use super::lines::Lines;
+use super::source_map::{FileId, SourceMap};
+use crate::lexer::token::TokenSpan;
+
+/// serializes a diagnostic as one line of JSON, spans are converted to byte
+/// offsets so tools can slice the file without knowing about chars
+pub fn diagnostic(diagnostic: &Diagnostic, sources: &SourceMap, file: FileId) -> String {
+    let lines = Lines::new(sources.text(file));
+
+    let severity = match diagnostic.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    let code = match diagnostic.code {
+        "" => "null".to_string(),
+        code => string(code),
+    };
+
+    let mut output = String::new();
+    write!(
+        output,
+        "{{\"severity\":\"{}\",\"code\":{},\"message\":{},\"file\":{},{}",
+        severity,
+        code,
+        string(&diagnostic.message),
+        string(sources.name(file)),
+        location(diagnostic.span, &lines),
+    )
+    .unwrap();
+
+    let labels: Vec<String> = diagnostic
+        .label
+        .iter()
+        .map(|label| {
+            format!(
+                "{{\"message\":{},{}}}",
+                string(label),
+                location(diagnostic.span, &lines)
+            )
+        })
+        .collect();
+    write!(output, ",\"labels\":[{}]", labels.join(",")).unwrap();
+
+    let trace: Vec<String> = diagnostic
+        .trace
+        .iter()
+        .map(|frame| {
+            format!(
+                "{{\"function\":{},{}}}",
+                string(&frame.function),
+                location(frame.span, &lines)
+            )
+        })
+        .collect();
+    write!(output, ",\"trace\":[{}]}}", trace.join(",")).unwrap();
+
+    output
+}
+
+/// the byte span and the 1-based line and columns of both ends, columns are
+/// counted in chars, UTF-8 bytes and UTF-16 code units
+fn location(span: TokenSpan, lines: &Lines) -> String {
+    let (start, end) = lines.resolve_span(span);
+
+    format!(
+        "\"span\":{{\"start\":{},\"end\":{}}},\
+         \"line\":{},\"column\":{},\"utf8_column\":{},\"utf16_column\":{},\
+         \"end_line\":{},\"end_column\":{},\"end_utf8_column\":{},\"end_utf16_column\":{}",
+        start.byte_offset,
+        end.byte_offset,
+        start.line,
+        start.column,
+        start.utf8_column,
+        start.utf16_column,
+        end.line,
+        end.column,
+        end.utf8_column,
+        end.utf16_column,
+    )
+}
+
+/// a quoted and escaped JSON string
+fn string(value: &str) -> String {
+    let mut output = String::with_capacity(value.len() + 2);
+    output.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => write!(output, "\\u{:04x}", c as u32).unwrap(),
+            c => output.push(c),
+        }
+    }
+
+    output.push('"');
+    output
+}
+
+#[test]
+fn test_json_diagnostic() {
+    use super::diagnostic::TraceFrame;
+
+    let mut sources = SourceMap::new();
+    let file = sources.add("a \"b\".rk", "// é\nchar c = 'xy';\n".to_string());
+
+    let error = Diagnostic::error(
+        "E0003",
+        "char literal must contain\n exactly one char".to_string(),
+        TokenSpan { start: 14, end: 18 },
+    )
+    .with_label("here");
+
+    let location = "\"span\":{\"start\":15,\"end\":19},\
+                    \"line\":2,\"column\":10,\"utf8_column\":10,\"utf16_column\":10,\
+                    \"end_line\":2,\"end_column\":14,\"end_utf8_column\":14,\"end_utf16_column\":14";
+    assert_eq!(
+        diagnostic(&error, &sources, file),
+        format!(
+            "{{\"severity\":\"error\",\"code\":\"E0003\",\
+             \"message\":\"char literal must contain\\n exactly one char\",\"file\":\"a \\\"b\\\".rk\",\
+             {},\"labels\":[{{\"message\":\"here\",{}}}],\"trace\":[]}}",
+            location, location
+        )
+    );
+
+    let warning = Diagnostic::warning("", "unused".to_string(), TokenSpan { start: 0, end: 0 });
+    let json = diagnostic(&warning, &sources, file);
+    assert!(json.starts_with("{\"severity\":\"warning\",\"code\":null,"));
+    assert!(json.ends_with("\"labels\":[],\"trace\":[]}"));
+
+    let runtime = Diagnostic::error(
+        "E0301",
+        "division by zero".to_string(),
+        TokenSpan { start: 5, end: 9 },
+    )
+    .with_trace(vec![TraceFrame {
+        function: "f".to_string(),
+        span: TokenSpan { start: 9, end: 12 },
+    }]);
+    let json = diagnostic(&runtime, &sources, file);
+    assert!(json.ends_with(
+        "\"trace\":[{\"function\":\"f\",\"span\":{\"start\":10,\"end\":13},\
+         \"line\":2,\"column\":5,\"utf8_column\":5,\"utf16_column\":5,\
+         \"end_line\":2,\"end_column\":8,\"end_utf8_column\":8,\"end_utf16_column\":8}]}"
+    ));
+}
@@ -0,0 +1,144 @@
+use crate::lexer::token::TokenSpan;
+
+/// where an offset is in the source, counted in the units different tools expect,
+/// ariadne counts chars, most editors bytes and LSP UTF-16 code units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    /// 1-based
+    pub line: usize,
+    /// 1-based, in chars
+    pub column: usize,
+    /// 1-based, in UTF-8 bytes
+    pub utf8_column: usize,
+    /// 1-based, in UTF-16 code units
+    pub utf16_column: usize,
+    /// from the start of the source, in UTF-8 bytes
+    pub byte_offset: usize,
+}
+
+/// maps the char offsets of spans to lines of the source
+#[derive(Debug)]
+pub struct Lines<'a> {
+    lines: Vec<&'a str>,
+    // char offset at which every line starts
+    starts: Vec<usize>,
+    // byte offset at which every line starts
+    byte_starts: Vec<usize>,
+}
+
+impl<'a> Lines<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let lines: Vec<&str> = source.split('\n').collect();
+
+        let mut starts = Vec::with_capacity(lines.len());
+        let mut byte_starts = Vec::with_capacity(lines.len());
+        let (mut offset, mut byte_offset) = (0, 0);
+        for line in &lines {
+            starts.push(offset);
+            byte_starts.push(byte_offset);
+            // the newline counts as a char of the line
+            offset += line.chars().count() + 1;
+            byte_offset += line.len() + 1;
+        }
+
+        Lines {
+            lines,
+            starts,
+            byte_starts,
+        }
+    }
+
+    /// 1-based line and column of a char offset
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let line = self.starts.partition_point(|start| *start <= offset);
+
+        (line, offset - self.starts[line - 1] + 1)
+    }
+
+    pub fn line(&self, offset: usize) -> usize {
+        self.position(offset).0
+    }
+
+    /// the position of a char offset in every unit, an offset behind
+    /// the end of its line, like the end of a span, is resolved as if
+    /// the line continued
+    pub fn resolve(&self, offset: usize) -> Position {
+        let (line, column) = self.position(offset);
+        let text = self.lines[line - 1];
+
+        let mut chars = text.chars();
+        let (mut utf8, mut utf16) = (0, 0);
+        for _ in 1..column {
+            match chars.next() {
+                Some(c) => {
+                    utf8 += c.len_utf8();
+                    utf16 += c.len_utf16();
+                }
+                // past the end, like the line break
+                None => {
+                    utf8 += 1;
+                    utf16 += 1;
+                }
+            }
+        }
+
+        Position {
+            line,
+            column,
+            utf8_column: utf8 + 1,
+            utf16_column: utf16 + 1,
+            byte_offset: self.byte_starts[line - 1] + utf8,
+        }
+    }
+
+    /// the positions of the start and the end of a span
+    pub fn resolve_span(&self, span: TokenSpan) -> (Position, Position) {
+        (self.resolve(span.start), self.resolve(span.end))
+    }
+
+    /// text of a 1-based line without the line break
+    pub fn text(&self, line: usize) -> &'a str {
+        self.lines[line - 1].trim_end_matches('\r')
+    }
+}
+
+#[test]
+fn test_positions() {
+    let lines = Lines::new("fn main() {\r\n    out!(\"é\");\n}");
+
+    assert_eq!(lines.position(0), (1, 1));
+    assert_eq!(lines.position(3), (1, 4));
+    assert_eq!(lines.position(13), (2, 1));
+    // offsets count chars, not bytes
+    assert_eq!(lines.position(25), (2, 13));
+    assert_eq!(lines.position(28), (3, 1));
+    assert_eq!(lines.text(1), "fn main() {");
+    assert_eq!(lines.text(3), "}");
+}
+
+#[test]
+fn test_resolve_units() {
+    // 'é' is 2 bytes and 1 UTF-16 unit, '😀' is 4 bytes and 2 UTF-16 units
+    let lines = Lines::new("// é\nString s = \"😀\" + x;\n");
+
+    let (start, end) = lines.resolve_span(TokenSpan { start: 22, end: 23 });
+    assert_eq!(
+        start,
+        Position {
+            line: 2,
+            column: 18,
+            utf8_column: 21,
+            utf16_column: 19,
+            byte_offset: 26,
+        }
+    );
+    assert_eq!(
+        (end.column, end.utf8_column, end.utf16_column),
+        (19, 22, 20)
+    );
+
+    // the line break and the empty line after it
+    assert_eq!(lines.resolve(24).utf8_column, 23);
+    assert_eq!(lines.resolve(25).line, 3);
+    assert_eq!(lines.resolve(25).byte_offset, 29);
+}
@@ -1,57 +1,190 @@
+pub mod codes;
+pub mod diagnostic;
+pub mod json;
+pub mod lines;
+pub mod source_map;
+
+use std::fmt::Write;
+use std::ops::Range;
+
+use self::diagnostic::{Diagnostic, Severity};
+use self::lines::Lines;
+use self::source_map::{FileId, SourceMap};
 use crate::lexer::token::TokenSpan;
-use ariadne::{Label, Report, ReportKind, Source};
-
-pub fn report_error(message: &str, file_path: &str, span: TokenSpan, opt_label: Option<&str>) {
-    let file_content = std::fs::read_to_string(file_path);
-
-    if file_content.is_err() {
-        eprintln!(
-            "Error: {} {}:{}-{} {}",
-            message,
-            file_path,
-            span.start,
-            span.end,
-            opt_label.unwrap_or("")
-        );
-        return;
+use ariadne::{Label, Report, ReportBuilder, ReportKind};
+
+type Span = (FileId, Range<usize>);
+
+/// how diagnostics are printed
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ErrorFormat {
+    /// rendered with the source lines they point at
+    #[default]
+    Human,
+    /// one JSON object per line on stderr, for editors and CI
+    Json,
+}
+
+/// prints the diagnostics of every source in the selected format
+#[derive(Debug, Default)]
+pub struct Reporter {
+    pub sources: SourceMap,
+    format: ErrorFormat,
+}
+
+impl Reporter {
+    pub fn new(format: ErrorFormat) -> Self {
+        Reporter {
+            sources: SourceMap::new(),
+            format,
+        }
+    }
+
+    pub fn report(&self, diagnostic: &Diagnostic, file: FileId) {
+        match self.format {
+            ErrorFormat::Human => {
+                build(diagnostic, file)
+                    .finish()
+                    .eprint(&self.sources)
+                    .unwrap();
+                if !diagnostic.trace.is_empty() {
+                    eprint!("{}", trace(diagnostic, &self.sources, file));
+                }
+            }
+            ErrorFormat::Json => {
+                eprintln!("{}", json::diagnostic(diagnostic, &self.sources, file))
+            }
+        }
     }
 
-    let file_content = file_content.unwrap();
+    /// reports an error which doesn't come as a `Diagnostic`
+    pub fn report_error(
+        &self,
+        code: &'static str,
+        message: &str,
+        file: FileId,
+        span: TokenSpan,
+        opt_label: Option<&str>,
+    ) {
+        let diagnostic = Diagnostic {
+            label: opt_label.map(str::to_string),
+            ..Diagnostic::error(code, message.to_string(), span)
+        };
 
-    Report::build(ReportKind::Error, file_path, span.start)
-        .with_message(message)
-        .with_label(
-            Label::new((file_path, span.start..span.end)).with_message(opt_label.unwrap_or("")),
-        )
-        .finish()
-        .print((file_path, Source::from(file_content.as_str())))
-        .unwrap();
+        self.report(&diagnostic, file);
+    }
 }
 
-#[allow(dead_code)]
-pub fn report_warning(message: &str, file_path: &str, span: TokenSpan, opt_label: Option<&str>) {
-    let file_content = std::fs::read_to_string(file_path);
-
-    if file_content.is_err() {
-        println!(
-            "Warning: {} {}:{}-{} {}",
-            message,
-            file_path,
-            span.start,
-            span.end,
-            opt_label.unwrap_or("")
-        );
-        return;
+fn build(diagnostic: &Diagnostic, file: FileId) -> ReportBuilder<'static, Span> {
+    let kind = match diagnostic.severity {
+        Severity::Error => ReportKind::Error,
+        Severity::Warning => ReportKind::Warning,
+    };
+    let span = diagnostic.span;
+    let label = diagnostic.label.as_deref().unwrap_or("");
+
+    let mut report = Report::build(kind, file, span.start)
+        .with_message(&diagnostic.message)
+        .with_label(Label::new((file, span.start..span.end)).with_message(label));
+    if !diagnostic.code.is_empty() {
+        report = report.with_code(diagnostic.code);
     }
 
-    let file_content = file_content.unwrap();
+    report
+}
+
+/// calls of a trace which are shown, a deep recursion would hide the error
+const TRACE_LIMIT: usize = 10;
+
+/// the calls of a runtime error with the line and column they were called at
+fn trace(diagnostic: &Diagnostic, sources: &SourceMap, file: FileId) -> String {
+    let lines = Lines::new(sources.text(file));
+    let mut output = String::new();
+    writeln!(output, "stack trace, innermost call first:").unwrap();
 
-    Report::build(ReportKind::Error, file_path, span.start)
-        .with_message(message)
-        .with_label(
-            Label::new((file_path, span.start..span.end)).with_message(opt_label.unwrap_or("")),
+    for frame in diagnostic.trace.iter().take(TRACE_LIMIT) {
+        let position = lines.resolve(frame.span.start);
+        writeln!(
+            output,
+            "    {} called at {}:{}:{}",
+            frame.function,
+            sources.name(file),
+            position.line,
+            position.column
         )
+        .unwrap();
+    }
+
+    if diagnostic.trace.len() > TRACE_LIMIT {
+        writeln!(
+            output,
+            "    ... {} more calls",
+            diagnostic.trace.len() - TRACE_LIMIT
+        )
+        .unwrap();
+    }
+
+    output
+}
+
+#[test]
+fn test_report_in_memory_source() {
+    let mut sources = SourceMap::new();
+    sources.add("a.rk", "fn main() {}\n".to_string());
+    let file = sources.add(
+        "<string>",
+        "fn main() {\n    int a = true;\n}\n".to_string(),
+    );
+
+    let error = Diagnostic::error(
+        "",
+        "expected int but found bool".to_string(),
+        TokenSpan { start: 24, end: 28 },
+    );
+
+    let mut output = Vec::new();
+    build(&error, file)
+        .with_config(ariadne::Config::default().with_color(false))
         .finish()
-        .print((file_path, Source::from(file_content.as_str())))
+        .write(&sources, &mut output)
         .unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("expected int but found bool"));
+    // the source is rendered from memory, with its own name and line numbers
+    assert!(output.contains("<string>:2:13"));
+    assert!(output.contains("int a = true;"));
+}
+
+#[test]
+fn test_render_trace() {
+    use self::diagnostic::TraceFrame;
+
+    let mut sources = SourceMap::new();
+    let file = sources.add("a.rk", "fn f() {}\nfn main() {\n    f();\n}\n".to_string());
+
+    let frame = |function: &str, start| TraceFrame {
+        function: function.to_string(),
+        span: TokenSpan {
+            start,
+            end: start + 1,
+        },
+    };
+    let mut error = Diagnostic::error(
+        "E0301",
+        "division by zero".to_string(),
+        TokenSpan { start: 0, end: 1 },
+    )
+    .with_trace(vec![frame("f", 26), frame("main", 13)]);
+
+    assert_eq!(
+        trace(&error, &sources, file),
+        "stack trace, innermost call first:\n    f called at a.rk:3:5\n    main called at a.rk:2:4\n"
+    );
+
+    // a deep recursion only shows its innermost calls
+    error.trace = vec![frame("f", 26); TRACE_LIMIT + 5];
+    let output = trace(&error, &sources, file);
+    assert_eq!(output.lines().count(), TRACE_LIMIT + 2);
+    assert!(output.ends_with("    ... 5 more calls\n"));
 }
@@ -0,0 +1,97 @@
+use std::fmt;
+use std::io;
+
+use ariadne::{Cache, Source};
+
+/// refers to a source in a `SourceMap`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileId(usize);
+
+#[derive(Debug)]
+struct SourceFile {
+    name: String,
+    text: String,
+    // lines of the text, prepared once for the reporter
+    source: Source,
+}
+
+/// owns the text of every source, so diagnostics never have to read a file again
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap::default()
+    }
+
+    /// adds a source which doesn't come from a file, like a string or stdin
+    pub fn add(&mut self, name: &str, text: String) -> FileId {
+        self.files.push(SourceFile {
+            name: name.to_string(),
+            source: Source::from(&text),
+            text,
+        });
+
+        FileId(self.files.len() - 1)
+    }
+
+    /// reads a file from disk, the path is used as its name
+    pub fn load(&mut self, path: &str) -> io::Result<FileId> {
+        let text = std::fs::read_to_string(path)?;
+
+        Ok(self.add(path, text))
+    }
+
+    /// appends text to a source and returns the char offset it starts at,
+    /// the REPL keeps all of its input in one source
+    pub fn append(&mut self, file: FileId, text: &str) -> usize {
+        let file = &mut self.files[file.0];
+        let offset = file.text.chars().count();
+
+        file.text.push_str(text);
+        file.source = Source::from(&file.text);
+
+        offset
+    }
+
+    pub fn name(&self, file: FileId) -> &str {
+        &self.files[file.0].name
+    }
+
+    pub fn text(&self, file: FileId) -> &str {
+        &self.files[file.0].text
+    }
+}
+
+impl Cache<FileId> for &SourceMap {
+    fn fetch(&mut self, id: &FileId) -> Result<&Source, Box<dyn fmt::Debug + '_>> {
+        match self.files.get(id.0) {
+            Some(file) => Ok(&file.source),
+            None => Err(Box::new(format!("unknown file {:?}", id))),
+        }
+    }
+
+    fn display<'a>(&self, id: &'a FileId) -> Option<Box<dyn fmt::Display + 'a>> {
+        let name = self.files.get(id.0)?.name.clone();
+
+        Some(Box::new(name))
+    }
+}
+
+#[test]
+fn test_source_map() {
+    let mut sources = SourceMap::new();
+
+    let string = sources.add("<string>", "int a = 1;".to_string());
+    let repl = sources.add("<repl>", String::new());
+
+    assert_eq!(sources.append(repl, "\"é\"\n"), 0);
+    assert_eq!(sources.append(repl, "1 + 2\n"), 4);
+
+    assert_eq!(sources.name(string), "<string>");
+    assert_eq!(sources.text(string), "int a = 1;");
+    assert_eq!(sources.text(repl), "\"é\"\n1 + 2\n");
+    assert!(sources.load("does/not/exist.rk").is_err());
+}
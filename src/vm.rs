@@ -0,0 +1,936 @@
+pub mod chunk;
+pub mod compiler;
+pub mod disassembler;
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use self::chunk::{Bytecode, Function, Op};
+use crate::builtins;
+use crate::interpreter::heap::{GcStats, Handle, Heap, Instance, Object};
+use crate::interpreter::value::Value;
+use crate::interpreter::{Interpreter, RuntimeError, MAX_CALL_DEPTH};
+use crate::lexer::token::TokenSpan;
+use crate::reporter::diagnostic::TraceFrame;
+
+/// a function invocation, its locals start at `base` on the value stack
+#[derive(Debug)]
+struct CallFrame {
+    function: Rc<Function>,
+    ip: usize,
+    base: usize,
+    kind: FrameKind,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FrameKind {
+    Function,
+    /// runs the field initializers for the `new` expression at the span
+    Init(TokenSpan),
+    /// returns the instance in slot 0 instead of its return value
+    Constructor,
+}
+
+/// stack based virtual machine which runs compiled programs,
+/// it behaves exactly like the tree walking interpreter
+pub struct Vm {
+    stack: Vec<Value>,
+    frames: Vec<CallFrame>,
+    // globals are defined in the order they are declared, `None` until then
+    globals: Vec<Option<Value>>,
+    heap: Heap,
+    output: Box<dyn Write>,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Vm::with_output(Box::new(io::stdout()))
+    }
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        Vm::default()
+    }
+
+    /// a vm which writes everything printed by `out!` to `output`
+    pub fn with_output(output: Box<dyn Write>) -> Self {
+        Vm {
+            stack: Vec::new(),
+            frames: Vec::new(),
+            globals: Vec::new(),
+            heap: Heap::new(),
+            output,
+        }
+    }
+
+    pub fn gc_stats(&mut self) -> &GcStats {
+        self.heap.stats()
+    }
+
+    /// initializes the globals and runs the `main` function
+    pub fn run(&mut self, bytecode: &Bytecode) -> Result<Value, RuntimeError> {
+        self.run_main(bytecode).map_err(|mut err| {
+            err.trace = self.trace(bytecode);
+            err
+        })
+    }
+
+    fn run_main(&mut self, bytecode: &Bytecode) -> Result<Value, RuntimeError> {
+        self.globals = vec![None; bytecode.globals.len()];
+
+        self.frames.push(CallFrame {
+            function: bytecode.script.clone(),
+            ip: 0,
+            base: 0,
+            kind: FrameKind::Function,
+        });
+        self.execute(bytecode)?;
+
+        let main = match bytecode.main {
+            Some(main) => main,
+            None => {
+                return Err(RuntimeError::at(
+                    "E0306",
+                    "no 'main' function found".to_string(),
+                    TokenSpan { start: 0, end: 0 },
+                ))
+            }
+        };
+
+        let span = bytecode.functions[main as usize].span;
+        self.call(bytecode, main, 0, span)?;
+        self.execute(bytecode)
+    }
+
+    /// runs until the outermost frame returns
+    fn execute(&mut self, bytecode: &Bytecode) -> Result<Value, RuntimeError> {
+        loop {
+            let frame = self.frames.last_mut().expect("no frame to execute");
+            let function = frame.function.clone();
+            let op = function.chunk.code[frame.ip];
+            let span = function.chunk.spans[frame.ip];
+            frame.ip += 1;
+
+            match op {
+                Op::Constant(index) => {
+                    self.push(function.chunk.constants[index as usize].clone());
+                }
+                Op::True => self.push(Value::Bool(true)),
+                Op::False => self.push(Value::Bool(false)),
+                Op::Null => self.push(Value::Null),
+                Op::Pop => {
+                    self.pop();
+                }
+                Op::Dup => self.push(self.peek(0).clone()),
+                Op::Dup2 => {
+                    self.push(self.peek(1).clone());
+                    self.push(self.peek(1).clone());
+                }
+                Op::GetLocal(slot) => {
+                    let value = self.stack[self.base() + slot as usize].clone();
+                    self.push(value);
+                }
+                Op::SetLocal(slot) => {
+                    let index = self.base() + slot as usize;
+                    self.stack[index] = self.peek(0).clone();
+                }
+                Op::GetGlobal(index) => {
+                    let value = self.global(bytecode, index, span)?.clone();
+                    self.push(value);
+                }
+                Op::SetGlobal(index) => {
+                    let value = self.peek(0).clone();
+                    *self.global(bytecode, index, span)? = value;
+                }
+                Op::DefineGlobal(index) => {
+                    self.globals[index as usize] = Some(self.peek(0).clone());
+                }
+                Op::Coerce(index) => {
+                    let value = self.pop();
+                    let ty = &function.chunk.types[index as usize];
+                    let value = Interpreter::coerce(&mut self.heap, value, ty, span)?;
+                    self.push(value);
+                }
+                Op::CoerceElement(slot) => {
+                    let array = match &self.stack[self.base() + slot as usize] {
+                        Value::Array(array) => *array,
+                        _ => unreachable!("loop variable without array"),
+                    };
+                    let ty = self.heap.array(array).ty.clone();
+
+                    let value = self.pop();
+                    let value = Interpreter::coerce(&mut self.heap, value, &ty, span)?;
+                    self.push(value);
+                }
+                Op::Unary(index) => {
+                    let right = self.pop();
+                    let operator = &function.chunk.operators[index as usize];
+                    let value = Interpreter::unary(&self.heap, operator, right)?;
+                    self.push(value);
+                }
+                Op::Binary(index) => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let operator = &function.chunk.operators[index as usize];
+                    let value = Interpreter::binary(&self.heap, left, operator, right)?;
+                    self.push(value);
+                }
+                Op::Jump(target) => self.jump(target),
+                Op::JumpIfFalse(target) => match self.pop() {
+                    Value::Bool(true) => {}
+                    Value::Bool(false) => self.jump(target),
+                    value => {
+                        return Err(RuntimeError::at(
+                            "E0206",
+                            format!(
+                                "expected bool condition but found {}",
+                                value.type_name(&self.heap)
+                            ),
+                            span,
+                        ))
+                    }
+                },
+                Op::And(target) | Op::Or(target) => {
+                    let (operator, short_circuit) = match op {
+                        Op::Or(_) => ("||", true),
+                        _ => ("&&", false),
+                    };
+
+                    match self.peek(0) {
+                        Value::Bool(b) if *b == short_circuit => self.jump(target),
+                        Value::Bool(_) => {
+                            self.pop();
+                        }
+                        value => {
+                            return Err(RuntimeError::at(
+                                "E0207",
+                                format!(
+                                    "cannot apply '{}' to {}",
+                                    operator,
+                                    value.type_name(&self.heap)
+                                ),
+                                span,
+                            ))
+                        }
+                    }
+                }
+                Op::Call(id, argc) => self.call(bytecode, id, argc as usize, span)?,
+                Op::Invoke(name, argc) => {
+                    let receiver = match self.peek(argc as usize) {
+                        Value::Object(instance) => *instance,
+                        _ => unreachable!("method call without object"),
+                    };
+                    let name = match &function.chunk.constants[name as usize] {
+                        Value::String(name) => name,
+                        _ => unreachable!("method name is not a string"),
+                    };
+
+                    let class = self.heap.instance(receiver).class.clone();
+                    let method = bytecode
+                        .class_ids
+                        .get(&class.name.lexeme)
+                        .and_then(|id| bytecode.classes[*id as usize].methods.get(name));
+
+                    match method {
+                        Some(method) => self.call(bytecode, *method, argc as usize, span)?,
+                        None => {
+                            return Err(RuntimeError::at(
+                                "E0204",
+                                format!("'{}' has no method '{}'", class.name.lexeme, name),
+                                span,
+                            ))
+                        }
+                    }
+                }
+                Op::New(id, argc) => self.instantiate(bytecode, id, argc as usize, span),
+                Op::Builtin(name, argc) => {
+                    let name = match &function.chunk.constants[name as usize] {
+                        Value::String(name) => name,
+                        _ => unreachable!("builtin name is not a string"),
+                    };
+                    let builtin = match builtins::lookup(name) {
+                        Some(builtin) => builtin,
+                        None => {
+                            return Err(RuntimeError::at(
+                                "E0205",
+                                format!("unknown builtin '{}!'", name),
+                                span,
+                            ))
+                        }
+                    };
+
+                    let arguments = self.stack.split_off(self.stack.len() - argc as usize);
+                    let value = (builtin.function)(self.output.as_mut(), &self.heap, arguments)
+                        .map_err(|message| RuntimeError::at("E0206", message, span))?;
+                    self.push(value);
+                }
+                Op::Return => {
+                    let frame = self.frames.pop().expect("return without frame");
+                    let value = self.pop();
+                    let value = match frame.kind {
+                        FrameKind::Constructor => self.stack[frame.base].clone(),
+                        _ => Interpreter::coerce(
+                            &mut self.heap,
+                            value,
+                            &frame.function.return_type,
+                            span,
+                        )?,
+                    };
+
+                    self.stack.truncate(frame.base);
+                    if self.frames.is_empty() {
+                        return Ok(value);
+                    }
+                    self.push(value);
+                }
+                Op::Construct(id) => {
+                    // the init frame is gone before the constructor is called,
+                    // so it doesn't count towards the call depth
+                    let frame = self.frames.pop().expect("construct without frame");
+                    let span = match frame.kind {
+                        FrameKind::Init(span) => span,
+                        _ => unreachable!("construct outside of an init function"),
+                    };
+                    let argc = self.stack.len() - frame.base - 1;
+                    let class = &bytecode.classes[id as usize];
+
+                    match class.constructor {
+                        Some(constructor) => {
+                            self.call(bytecode, constructor, argc, span)?;
+                            if let Some(frame) = self.frames.last_mut() {
+                                frame.kind = FrameKind::Constructor;
+                            }
+                        }
+                        None if argc > 0 => {
+                            return Err(RuntimeError::at(
+                                "E0212",
+                                format!(
+                                    "'{}' has no constructor taking {} arguments",
+                                    class.decl.name.lexeme, argc
+                                ),
+                                span,
+                            ))
+                        }
+                        None => {}
+                    }
+                }
+                Op::CheckObject => {
+                    if !matches!(self.peek(0), Value::Object(_)) {
+                        return Err(RuntimeError::at(
+                            "E0206",
+                            format!(
+                                "expected an object but found {}",
+                                self.peek(0).type_name(&self.heap)
+                            ),
+                            span,
+                        ));
+                    }
+                }
+                Op::GetField(name) => {
+                    let instance = self.pop_instance();
+                    let name = match &function.chunk.constants[name as usize] {
+                        Value::String(name) => name,
+                        _ => unreachable!("field name is not a string"),
+                    };
+
+                    let instance = self.heap.instance(instance);
+                    match instance.fields.get(name) {
+                        Some(value) => {
+                            let value = value.clone();
+                            self.push(value);
+                        }
+                        None => {
+                            return Err(RuntimeError::at(
+                                "E0204",
+                                format!("'{}' has no field '{}'", instance.class.name.lexeme, name),
+                                span,
+                            ))
+                        }
+                    }
+                }
+                Op::SetField(name) => {
+                    let value = self.pop();
+                    let instance = self.pop_instance();
+                    let name = match &function.chunk.constants[name as usize] {
+                        Value::String(name) => name,
+                        _ => unreachable!("field name is not a string"),
+                    };
+
+                    let class = self.heap.instance(instance).class.clone();
+                    let ty = match class.find_field(name) {
+                        Some(field) => &field.ty,
+                        None => {
+                            return Err(RuntimeError::at(
+                                "E0204",
+                                format!("'{}' has no field '{}'", class.name.lexeme, name),
+                                span,
+                            ))
+                        }
+                    };
+
+                    let value = Interpreter::coerce(&mut self.heap, value, ty, span)?;
+                    self.heap
+                        .instance_mut(instance)
+                        .fields
+                        .insert(name.clone(), value.clone());
+                    self.push(value);
+                }
+                Op::CheckArray => {
+                    if !matches!(self.peek(0), Value::Array(_)) {
+                        return Err(RuntimeError::at(
+                            "E0206",
+                            format!(
+                                "expected an array but found {}",
+                                self.peek(0).type_name(&self.heap)
+                            ),
+                            span,
+                        ));
+                    }
+                }
+                Op::CheckIndex => {
+                    self.index(0, span)?;
+                }
+                Op::Index => {
+                    let (array, i) = self.index(0, span)?;
+                    self.stack.truncate(self.stack.len() - 2);
+                    self.push(self.heap.array(array).elements[i].clone());
+                }
+                Op::SetIndex => {
+                    let value = self.pop();
+                    let (array, i) = self.index(0, span)?;
+                    self.stack.truncate(self.stack.len() - 2);
+
+                    let ty = self.heap.array(array).ty.clone();
+                    let value = Interpreter::coerce(&mut self.heap, value, &ty, span)?;
+                    self.heap.array_mut(array).elements[i] = value.clone();
+                    self.push(value);
+                }
+                Op::Array(count) => {
+                    // the elements stay on the stack until the array is allocated, so they are rooted
+                    let values = self.stack[self.stack.len() - count as usize..].to_vec();
+                    let array = Interpreter::array_literal(&mut self.heap, values, span)?;
+                    let array = self.allocate(Object::Array(array));
+
+                    self.stack.truncate(self.stack.len() - count as usize);
+                    self.push(Value::Array(array));
+                }
+                Op::ForNext(slot, target) => {
+                    let slot = self.base() + slot as usize;
+                    let array = match &self.stack[slot] {
+                        Value::Array(array) => *array,
+                        value => {
+                            return Err(RuntimeError::at(
+                                "E0206",
+                                format!(
+                                    "expected an array but found {}",
+                                    value.type_name(&self.heap)
+                                ),
+                                span,
+                            ))
+                        }
+                    };
+                    let i = match self.stack[slot + 1] {
+                        Value::Int(i) => i as usize,
+                        _ => unreachable!("loop index is not an int"),
+                    };
+
+                    // the body can assign to the elements, so they are read one at a time
+                    match self.heap.array(array).elements.get(i) {
+                        Some(element) => {
+                            self.stack[slot + 2] = element.clone();
+                            self.stack[slot + 1] = Value::Int(i as i64 + 1);
+                        }
+                        None => self.jump(target),
+                    }
+                }
+            }
+        }
+    }
+
+    /// the calls which are still running after an error, every frame was
+    /// called by the instruction its caller stopped at. like in the interpreter
+    /// the script and the field initializers aren't calls
+    fn trace(&self, bytecode: &Bytecode) -> Vec<TraceFrame> {
+        let mut trace = Vec::new();
+
+        for (i, frame) in self.frames.iter().enumerate().rev() {
+            if matches!(frame.kind, FrameKind::Init(_))
+                || Rc::ptr_eq(&frame.function, &bytecode.script)
+            {
+                continue;
+            }
+
+            let span = match i {
+                // `main` is called by the vm itself
+                0 => frame.function.span,
+                _ => {
+                    let caller = &self.frames[i - 1];
+                    caller.function.chunk.spans[caller.ip - 1]
+                }
+            };
+            trace.push(TraceFrame {
+                function: frame.function.name.clone(),
+                span,
+            });
+        }
+
+        trace
+    }
+
+    /// pushes a frame for the function, its arguments are on top of the stack
+    /// and the object of a method is below them
+    fn call(
+        &mut self,
+        bytecode: &Bytecode,
+        id: u32,
+        argc: usize,
+        span: TokenSpan,
+    ) -> Result<(), RuntimeError> {
+        let function = bytecode.functions[id as usize].clone();
+
+        if argc != function.params.len() {
+            return Err(RuntimeError::at(
+                "E0212",
+                format!(
+                    "expected {} arguments but got {}",
+                    function.params.len(),
+                    argc
+                ),
+                span,
+            ));
+        }
+
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::at(
+                "E0305",
+                "stack overflow".to_string(),
+                span,
+            ));
+        }
+
+        let start = self.stack.len() - argc;
+        for (i, ty) in function.params.iter().enumerate() {
+            let value = std::mem::replace(&mut self.stack[start + i], Value::Null);
+            self.stack[start + i] = Interpreter::coerce(&mut self.heap, value, ty, span)?;
+        }
+
+        let base = start - usize::from(function.has_this);
+        self.frames.push(CallFrame {
+            function,
+            ip: 0,
+            base,
+            kind: FrameKind::Function,
+        });
+
+        Ok(())
+    }
+
+    /// creates a new instance below the arguments and runs the init function
+    /// of the class, like the field initializers of the interpreter its frame
+    /// isn't limited by the call depth
+    fn instantiate(&mut self, bytecode: &Bytecode, id: u32, argc: usize, span: TokenSpan) {
+        let class = &bytecode.classes[id as usize];
+
+        let instance = self.allocate(Object::Instance(Instance {
+            class: class.decl.clone(),
+            fields: HashMap::new(),
+        }));
+        let base = self.stack.len() - argc;
+        self.stack.insert(base, Value::Object(instance));
+
+        self.frames.push(CallFrame {
+            function: bytecode.functions[class.init as usize].clone(),
+            ip: 0,
+            base,
+            kind: FrameKind::Init(span),
+        });
+    }
+
+    /// a global which is already defined, a function called by the initializer
+    /// of an earlier global can reach it before
+    fn global(
+        &mut self,
+        bytecode: &Bytecode,
+        index: u32,
+        span: TokenSpan,
+    ) -> Result<&mut Value, RuntimeError> {
+        self.globals[index as usize].as_mut().ok_or_else(|| {
+            RuntimeError::at(
+                "E0202",
+                format!("undefined variable '{}'", bytecode.globals[index as usize]),
+                span,
+            )
+        })
+    }
+
+    /// checks the index `depth` values below the top of the stack and the array below it
+    fn index(&self, depth: usize, span: TokenSpan) -> Result<(Handle, usize), RuntimeError> {
+        let array = match self.peek(depth + 1) {
+            Value::Array(array) => *array,
+            _ => unreachable!("index without array"),
+        };
+
+        let i = match self.peek(depth) {
+            Value::Int(i) => *i,
+            value => {
+                return Err(RuntimeError::at(
+                    "E0206",
+                    format!(
+                        "expected int index but found {}",
+                        value.type_name(&self.heap)
+                    ),
+                    span,
+                ))
+            }
+        };
+
+        let len = self.heap.array(array).elements.len();
+        match usize::try_from(i) {
+            Ok(i) if i < len => Ok((array, i)),
+            _ => Err(RuntimeError::at(
+                "E0304",
+                format!("index {} out of bounds for length {}", i, len),
+                span,
+            )),
+        }
+    }
+
+    fn allocate(&mut self, object: Object) -> Handle {
+        let handle = self.heap.allocate(object);
+
+        // every reachable value is on the stack or in a global
+        if self.heap.should_collect() {
+            let values = self.stack.iter().chain(self.globals.iter().flatten());
+            let roots = values.filter_map(Value::handle).chain([handle]);
+            self.heap.collect(roots);
+        }
+
+        handle
+    }
+
+    fn base(&self) -> usize {
+        self.frames.last().map_or(0, |frame| frame.base)
+    }
+
+    fn jump(&mut self, target: u32) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.ip = target as usize;
+        }
+    }
+
+    fn push(&mut self, value: Value) {
+        self.stack.push(value);
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("value stack underflow")
+    }
+
+    fn pop_instance(&mut self) -> Handle {
+        match self.pop() {
+            Value::Object(instance) => instance,
+            _ => unreachable!("field access without object"),
+        }
+    }
+
+    fn peek(&self, distance: usize) -> &Value {
+        &self.stack[self.stack.len() - 1 - distance]
+    }
+}
+
+/// runs the source with both engines and checks that they behave the same,
+/// returns the output and the result of the vm
+#[cfg(test)]
+fn run_both(source: &str, heap: fn() -> Heap) -> (String, Result<Value, RuntimeError>) {
+    use crate::interpreter::SharedBuffer;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    let tokens = Lexer::from_string(source.to_string()).lex_tokens().0;
+    let program = Parser::new(tokens).parse().unwrap();
+
+    let tree_buffer = SharedBuffer::default();
+    let tree_result = Interpreter::with_output(Box::new(tree_buffer.clone())).interpret(&program);
+
+    let bytecode = compiler::compile(&program).unwrap();
+    let vm_buffer = SharedBuffer::default();
+    let mut vm = Vm::with_output(Box::new(vm_buffer.clone()));
+    vm.heap = heap();
+    let vm_result = vm.run(&bytecode);
+
+    let tree_output = String::from_utf8_lossy(&tree_buffer.0.borrow()).into_owned();
+    let vm_output = String::from_utf8_lossy(&vm_buffer.0.borrow()).into_owned();
+    assert_eq!(tree_output, vm_output);
+
+    match (&tree_result, &vm_result) {
+        // handles are only equal by chance, the heaps are laid out differently
+        (Ok(tree), Ok(value)) if tree.handle().is_none() => assert_eq!(tree, value),
+        (Ok(tree), Ok(value)) => assert!(value.handle().is_some(), "{:?} and {:?}", tree, value),
+        (Err(tree), Err(err)) => {
+            assert_eq!(tree.code, err.code);
+            assert_eq!(tree.message, err.message);
+            let trace = |err: &RuntimeError| {
+                err.trace
+                    .iter()
+                    .map(|frame| (frame.function.clone(), frame.span.start, frame.span.end))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(trace(tree), trace(err));
+            assert_eq!(
+                (tree.span.start, tree.span.end),
+                (err.span.start, err.span.end)
+            );
+        }
+        _ => panic!("engines disagree: {:?} and {:?}", tree_result, vm_result),
+    }
+
+    (vm_output, vm_result)
+}
+
+#[test]
+fn test_vm_matches_interpreter() {
+    let source = "
+        int calls = 0;
+        String greeting = \"hi\" + 1;
+
+        class Counter {
+            int count = 1;
+            String name;
+
+            Counter(String name) {
+                this.name = name;
+            }
+
+            fn add(int n) > int {
+                calls += 1;
+                this.count += n;
+                return this.count;
+            }
+        }
+
+        fn fib(int n) > int {
+            if (n < 2) {
+                return n;
+            }
+
+            return fib(n - 1) + fib(n - 2);
+        }
+
+        fn main() > double {
+            Counter c = new Counter(\"c\");
+            c.add(2);
+            out!(c.name, c.add(3), \" \", greeting, \" \", fib(15));
+
+            bool a = false && c.add(1) > 0;
+            bool b = true || c.add(1) > 0;
+            out!(a, b, calls);
+
+            double total = 0;
+            for (int i = 0; i < 10; i += 1) {
+                if (i % 2 == 0) {
+                    continue_with(i);
+                } else {
+                    total += i / 2;
+                }
+            }
+
+            int x = 1;
+            {
+                int x = 2;
+                x **= 3;
+                total -= x;
+            }
+            return total + x;
+        }
+
+        fn continue_with(int i) {
+            out!(i);
+        }
+    ";
+
+    let (output, result) = run_both(source, Heap::new);
+    assert_eq!(output, "c6 hi1 610\nfalsetrue2\n0\n2\n4\n6\n8\n");
+    assert_eq!(result.unwrap(), Value::Double(3.0));
+}
+
+#[test]
+fn test_vm_arrays() {
+    let source = "
+        fn sum(int[] values) > int {
+            int total = 0;
+            for value in values {
+                total += value;
+                value = 0;
+            }
+            return total;
+        }
+
+        fn main() {
+            int[] arr = [0, 1, 2, 3];
+            arr[1] = 10;
+            arr[2] *= 5;
+
+            int[][] nested = [[], [1, 2]];
+            nested[0] = arr;
+            nested[0][3] = 4;
+
+            double[] mixed = [1, 2.5];
+            for d in mixed {
+                d += 1;
+                out!(d);
+            }
+
+            out!(arr, \" \", sum(arr), \" \", len!(arr), \" \", len!(\"héllo\"));
+            out!(nested, \" \", mixed, \" \", len!([]));
+        }
+    ";
+
+    let (output, _) = run_both(source, Heap::new);
+    assert_eq!(
+        output,
+        "2.0\n3.5\n[0, 10, 10, 4] 24 4 5\n[[0, 10, 10, 4], [1, 2]] [1.0, 2.5] 0\n"
+    );
+}
+
+#[test]
+fn test_vm_runtime_errors() {
+    let errors = [
+        ("fn main() { int a = 1 / 0; }", "division by zero"),
+        (
+            "fn main() { int[] a = [1]; a[0 + 1] += 2; }",
+            "index 1 out of bounds for length 1",
+        ),
+        (
+            "class A { int x; } fn main() { A a; a.x = 1; }",
+            "expected an object but found null",
+        ),
+        (
+            "fn f(int n) > int { return f(n + 1); } fn main() { f(0); }",
+            "stack overflow",
+        ),
+        (
+            "fn main() { int a = 9223372036854775807; a += 1; }",
+            "integer overflow",
+        ),
+        ("fn foo() {}", "no 'main' function found"),
+        // globals are defined one at a time, a function can run before a later one is
+        (
+            "int a = f(); int b = 2; fn f() > int { return b; } fn main() {}",
+            "undefined variable 'b'",
+        ),
+        (
+            "int a = f(); int b = 2; fn f() > int { b = 3; return 1; } fn main() {}",
+            "undefined variable 'b'",
+        ),
+        // every nested statement costs native stack in the interpreter
+        (
+            "fn f(int n) > int {
+                while (true) {
+                    for (int i = 0; i < 1; i += 1) {
+                        if (n >= 0) {
+                            {
+                                return 1 + (2 * (3 + f(n + 1)));
+                            }
+                        }
+                    }
+                }
+                return 0;
+            }
+            fn main() { f(0); }",
+            "stack overflow",
+        ),
+        // the fields are initialized before the arguments are checked
+        (
+            "class A { int x = f(); } fn f() > int { out!(1); return 1; } fn main() { new A(1); }",
+            "'A' has no constructor taking 1 arguments",
+        ),
+        (
+            "class A { int x = f(); A(int a) {} } fn f() > int { out!(1); return 1; } fn main() { new A(); }",
+            "expected 1 arguments but got 0",
+        ),
+        // both count the constructors and field initializers towards the call depth alike
+        (
+            "class A { A() { out!(1); new A(); } } fn main() { new A(); }",
+            "stack overflow",
+        ),
+        (
+            "class N { N next = make(); } fn make() > N { out!(1); return new N(); } fn main() { make(); }",
+            "stack overflow",
+        ),
+    ];
+
+    // the tree walker needs more than the default stack of a test thread to reach the limit,
+    // as much as `rawk` itself runs with
+    let thread = std::thread::Builder::new().stack_size(crate::interpreter::STACK_SIZE);
+    let handle = thread.spawn(move || {
+        for (source, message) in errors {
+            let (_, result) = run_both(source, Heap::new);
+            assert_eq!(result.unwrap_err().message, message);
+        }
+    });
+    handle.unwrap().join().unwrap();
+}
+
+#[test]
+fn test_vm_stack_trace() {
+    let source = "
+        fn div(int a, int b) > int {
+            return a / b;
+        }
+
+        class Calc {
+            int zero = div(1, 0);
+        }
+
+        fn main() {
+            new Calc();
+        }
+    ";
+
+    let (_, result) = run_both(source, Heap::new);
+    let err = result.unwrap_err();
+
+    // the field initializer isn't a call of its own
+    let trace: Vec<(&str, &str)> = err
+        .trace
+        .iter()
+        .map(|frame| {
+            let text = &source[frame.span.start..frame.span.end];
+            (frame.function.as_str(), text)
+        })
+        .collect();
+    assert_eq!(trace, vec![("div", "div(1, 0)"), ("main", "main")]);
+}
+
+#[test]
+fn test_vm_garbage_collection() {
+    let source = "
+        class Node {
+            Node next;
+            int[] values = [1, 2];
+        }
+
+        fn cycle() > Node {
+            Node a = new Node();
+            a.next = new Node();
+            a.next.next = a;
+            return a;
+        }
+
+        fn main() {
+            Node kept = cycle();
+            for (int i = 0; i < 100; i += 1) {
+                cycle();
+            }
+
+            int[][] nested = [[len!([1, 2, 3])], [kept.values[0] + cycle().values[1]]];
+            out!(nested, \" \", kept.next.next == kept, \" \", [new Node(), new Node()][1].values);
+        }
+    ";
+
+    // collect on every allocation, so a value missing from the stack is freed
+    let (output, _) = run_both(source, Heap::stress);
+    assert_eq!(output, "[[3], [3]] true [1, 2]\n");
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::interpreter::value::Value;
+use crate::lexer::token::{Token, TokenSpan};
+use crate::parser::ast::{ClassDecl, Type};
+
+/// a single instruction, operands are indices into the tables of the chunk
+/// or the program, jump targets are instruction indices
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Constant(u32),
+    True,
+    False,
+    Null,
+    Pop,
+    Dup,
+    /// duplicates the two values on top of the stack
+    Dup2,
+
+    GetLocal(u32),
+    SetLocal(u32),
+    /// fails if the global isn't defined yet
+    GetGlobal(u32),
+    /// fails if the global isn't defined yet
+    SetGlobal(u32),
+    /// gives a global its initial value, which makes it usable
+    DefineGlobal(u32),
+    /// converts the value on top of the stack to a type of the types table
+    Coerce(u32),
+    /// converts the value on top of the stack to the element type of the array in a local
+    CoerceElement(u32),
+
+    Unary(u32),
+    Binary(u32),
+
+    Jump(u32),
+    /// pops the condition
+    JumpIfFalse(u32),
+    /// `&&`, keeps the left operand if it decides the result
+    And(u32),
+    /// `||`, keeps the left operand if it decides the result
+    Or(u32),
+
+    /// calls a function of the program with the given number of arguments
+    Call(u32, u32),
+    /// calls a method by name on the object below the arguments
+    Invoke(u32, u32),
+    /// creates an instance of a class of the program
+    New(u32, u32),
+    /// calls the builtin named by a constant
+    Builtin(u32, u32),
+    Return,
+    /// ends the init function of a class, then checks the arguments and calls
+    /// the constructor, the new instance is the result
+    Construct(u32),
+
+    /// fails unless the value on top of the stack is an object
+    CheckObject,
+    GetField(u32),
+    SetField(u32),
+
+    /// fails unless the value on top of the stack is an array
+    CheckArray,
+    /// fails unless the index on top of the stack is valid for the array below it
+    CheckIndex,
+    Index,
+    SetIndex,
+    /// builds an array from the given number of values on the stack
+    Array(u32),
+    /// stores the next element of the array in local `slot` into local `slot + 2`,
+    /// local `slot + 1` holds the index, jumps to the target after the last element
+    ForNext(u32, u32),
+}
+
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<Op>,
+    /// source span of every instruction, used for errors
+    pub spans: Vec<TokenSpan>,
+    pub constants: Vec<Value>,
+    /// tokens of unary and binary operators, which are needed for error messages
+    pub operators: Vec<Token>,
+    pub types: Vec<Type>,
+}
+
+impl Chunk {
+    pub fn write(&mut self, op: Op, span: TokenSpan) -> usize {
+        self.code.push(op);
+        self.spans.push(span);
+        self.code.len() - 1
+    }
+
+    pub fn add_constant(&mut self, value: Value) -> u32 {
+        match self.constants.iter().position(|c| *c == value) {
+            Some(index) => index as u32,
+            None => {
+                self.constants.push(value);
+                self.constants.len() as u32 - 1
+            }
+        }
+    }
+
+    pub fn add_operator(&mut self, operator: Token) -> u32 {
+        self.operators.push(operator);
+        self.operators.len() as u32 - 1
+    }
+
+    pub fn add_type(&mut self, ty: Type) -> u32 {
+        match self.types.iter().position(|t| *t == ty) {
+            Some(index) => index as u32,
+            None => {
+                self.types.push(ty);
+                self.types.len() as u32 - 1
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Function {
+    pub name: String,
+    /// span of the name, used for errors about the function itself
+    pub span: TokenSpan,
+    pub params: Vec<Type>,
+    /// methods get the object as an extra local in slot 0
+    pub has_this: bool,
+    pub return_type: Type,
+    pub chunk: Chunk,
+}
+
+#[derive(Debug)]
+pub struct Class {
+    pub decl: Rc<ClassDecl>,
+    /// runs the field initializers, the arguments aren't checked until it ends
+    pub init: u32,
+    pub constructor: Option<u32>,
+    pub methods: HashMap<String, u32>,
+}
+
+/// a compiled program, the script initializes the globals
+#[derive(Debug)]
+pub struct Bytecode {
+    pub functions: Vec<Rc<Function>>,
+    pub classes: Vec<Class>,
+    pub class_ids: HashMap<String, u32>,
+    pub globals: Vec<String>,
+    pub script: Rc<Function>,
+    pub main: Option<u32>,
+}
@@ -0,0 +1,715 @@
+use std::collections::HashMap;
+use std::mem;
+use std::rc::Rc;
+
+use super::chunk::{Bytecode, Chunk, Class, Function, Op};
+use crate::interpreter::value::Value;
+use crate::interpreter::Interpreter;
+use crate::lexer::token::{Token, TokenSpan, TokenType};
+use crate::parser::ast::{ClassDecl, Decl, Expr, FunctionDecl, Program, Stmt, Type, VarDecl};
+
+#[derive(Debug)]
+pub struct CompileError {
+    pub code: &'static str,
+    pub message: String,
+    pub span: TokenSpan,
+}
+
+type CompileResult<T> = Result<T, CompileError>;
+
+/// the type values stored in a variable are converted to
+#[derive(Debug, Clone)]
+enum SlotType {
+    Typed(Type),
+    /// a `for ... in` variable, which has the element type of the array in the given local
+    Element(u32),
+}
+
+#[derive(Debug)]
+struct Local {
+    name: String,
+    ty: SlotType,
+    depth: usize,
+}
+
+/// the function which is currently compiled
+#[derive(Debug, Default)]
+struct FunctionState {
+    chunk: Chunk,
+    locals: Vec<Local>,
+    depth: usize,
+    has_this: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Compiler {
+    function_ids: HashMap<String, u32>,
+    class_ids: HashMap<String, u32>,
+    globals: HashMap<String, (u32, Type)>,
+    state: FunctionState,
+}
+
+/// compiles a checked program to bytecode
+pub fn compile(program: &Program) -> CompileResult<Bytecode> {
+    Compiler::default().compile(program)
+}
+
+impl Compiler {
+    fn compile(mut self, program: &Program) -> CompileResult<Bytecode> {
+        let mut function_decls = Vec::new();
+        let mut class_decls = Vec::new();
+        let mut globals = Vec::new();
+
+        // every function and method gets an id up front, so calls can be compiled in any order
+        for decl in &program.declarations {
+            match decl {
+                Decl::Function(function) => {
+                    self.function_ids
+                        .insert(function.name.lexeme.clone(), function_decls.len() as u32);
+                    function_decls.push((function.clone(), false));
+                }
+                Decl::Class(class) => {
+                    self.class_ids
+                        .insert(class.name.lexeme.clone(), class_decls.len() as u32);
+                    class_decls.push(class.clone());
+                }
+                Decl::Var(var) => {
+                    let index = globals.len() as u32;
+                    self.globals
+                        .insert(var.name.lexeme.clone(), (index, var.ty.clone()));
+                    globals.push(var.name.lexeme.clone());
+                }
+            }
+        }
+
+        let mut classes = Vec::new();
+        for class in &class_decls {
+            let mut methods = HashMap::new();
+            for method in &class.methods {
+                methods.insert(method.name.lexeme.clone(), function_decls.len() as u32);
+                function_decls.push((method.clone(), true));
+            }
+
+            // the init function comes last, it's compiled separately
+            classes.push(Class {
+                decl: class.clone(),
+                init: 0,
+                constructor: None,
+                methods,
+            });
+        }
+
+        let mut functions = Vec::new();
+        for (decl, has_this) in &function_decls {
+            functions.push(Rc::new(self.function(decl, *has_this)?));
+        }
+
+        for (id, (class, decl)) in classes.iter_mut().zip(&class_decls).enumerate() {
+            if let Some(constructor) = &decl.constructor {
+                functions.push(Rc::new(self.function(constructor, true)?));
+                class.constructor = Some(functions.len() as u32 - 1);
+            }
+
+            functions.push(Rc::new(self.init(decl, id as u32)?));
+            class.init = functions.len() as u32 - 1;
+        }
+
+        let script = self.script(program)?;
+
+        Ok(Bytecode {
+            main: self.function_ids.get("main").copied(),
+            functions,
+            classes,
+            class_ids: self.class_ids,
+            globals,
+            script: Rc::new(script),
+        })
+    }
+
+    /// the script initializes the globals in the order they are declared
+    fn script(&mut self, program: &Program) -> CompileResult<Function> {
+        self.state = FunctionState::default();
+
+        for decl in &program.declarations {
+            if let Decl::Var(var) = decl {
+                self.initial_value(var)?;
+
+                let (index, _) = self.globals[&var.name.lexeme];
+                self.emit(Op::DefineGlobal(index), var.name.span);
+                self.emit(Op::Pop, var.name.span);
+            }
+        }
+
+        let span = TokenSpan { start: 0, end: 0 };
+        self.emit(Op::Null, span);
+        self.emit(Op::Return, span);
+
+        Ok(self.finish("<script>".to_string(), span, Vec::new(), Type::Void))
+    }
+
+    fn function(&mut self, decl: &FunctionDecl, has_this: bool) -> CompileResult<Function> {
+        self.begin_function(has_this);
+
+        for param in &decl.params {
+            self.add_local(&param.name.lexeme, SlotType::Typed(param.ty.clone()));
+        }
+
+        for stmt in &decl.body {
+            self.stmt(stmt)?;
+        }
+
+        // falling off the end returns null, the checker makes sure that's only allowed without return type
+        self.emit(Op::Null, decl.name.span);
+        self.emit(Op::Return, decl.name.span);
+
+        let params = decl.params.iter().map(|p| p.ty.clone()).collect();
+        Ok(self.finish(
+            decl.name.lexeme.clone(),
+            decl.name.span,
+            params,
+            decl.return_type.clone(),
+        ))
+    }
+
+    /// runs the field initializers of a new instance in slot 0, the arguments
+    /// of the constructor are above it and only checked by `Construct`, as
+    /// the interpreter checks them after the fields
+    fn init(&mut self, class: &ClassDecl, id: u32) -> CompileResult<Function> {
+        self.begin_function(true);
+
+        for field in &class.fields {
+            let name = self.constant(Value::String(field.name.lexeme.clone()));
+
+            self.emit(Op::GetLocal(0), field.name.span);
+            match &field.initializer {
+                Some(expr) => self.expr(expr)?,
+                None => self.default_value(&field.ty, field.name.span),
+            }
+            self.emit(Op::SetField(name), field.name.span);
+            self.emit(Op::Pop, field.name.span);
+        }
+
+        self.emit(Op::Construct(id), class.name.span);
+
+        Ok(self.finish(
+            format!("{}.<init>", class.name.lexeme),
+            class.name.span,
+            Vec::new(),
+            Type::Class(class.name.lexeme.clone()),
+        ))
+    }
+
+    fn begin_function(&mut self, has_this: bool) {
+        self.state = FunctionState {
+            has_this,
+            ..FunctionState::default()
+        };
+
+        if has_this {
+            self.add_local("this", SlotType::Typed(Type::Void));
+        }
+    }
+
+    fn finish(
+        &mut self,
+        name: String,
+        span: TokenSpan,
+        params: Vec<Type>,
+        return_type: Type,
+    ) -> Function {
+        let state = mem::take(&mut self.state);
+
+        Function {
+            name,
+            span,
+            params,
+            has_this: state.has_this,
+            return_type,
+            chunk: state.chunk,
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> CompileResult<()> {
+        match stmt {
+            Stmt::Expression(expr) => {
+                self.expr(expr)?;
+                self.emit(Op::Pop, expr.span());
+            }
+            Stmt::Var(var) => {
+                // the initializer can't see the new variable yet
+                self.initial_value(var)?;
+                self.add_local(&var.name.lexeme, SlotType::Typed(var.ty.clone()));
+            }
+            Stmt::Block(statements) => {
+                self.begin_scope();
+                for stmt in statements {
+                    self.stmt(stmt)?;
+                }
+                self.end_scope();
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition)?;
+                let else_jump = self.emit(Op::JumpIfFalse(0), condition.span());
+
+                self.stmt(then_branch)?;
+                let end_jump = self.emit(Op::Jump(0), condition.span());
+
+                self.patch(else_jump);
+                if let Some(else_branch) = else_branch {
+                    self.stmt(else_branch)?;
+                }
+                self.patch(end_jump);
+            }
+            Stmt::While { condition, body } => {
+                let start = self.state.chunk.code.len() as u32;
+
+                self.expr(condition)?;
+                let exit_jump = self.emit(Op::JumpIfFalse(0), condition.span());
+
+                self.stmt(body)?;
+                self.emit(Op::Jump(start), condition.span());
+
+                self.patch(exit_jump);
+            }
+            Stmt::For {
+                initializer,
+                condition,
+                increment,
+                body,
+            } => {
+                // the initializer gets its own scope around the whole loop
+                self.begin_scope();
+                if let Some(initializer) = initializer {
+                    self.stmt(initializer)?;
+                }
+
+                let start = self.state.chunk.code.len() as u32;
+                let exit_jump = match condition {
+                    Some(condition) => {
+                        self.expr(condition)?;
+                        Some(self.emit(Op::JumpIfFalse(0), condition.span()))
+                    }
+                    None => None,
+                };
+
+                self.stmt(body)?;
+                if let Some(increment) = increment {
+                    self.expr(increment)?;
+                    self.emit(Op::Pop, increment.span());
+                }
+                let span = self.last_span();
+                self.emit(Op::Jump(start), span);
+
+                if let Some(exit_jump) = exit_jump {
+                    self.patch(exit_jump);
+                }
+                self.end_scope();
+            }
+            Stmt::ForIn {
+                name,
+                iterable,
+                body,
+            } => {
+                self.begin_scope();
+
+                // the array and the index live in hidden locals next to the loop variable
+                self.expr(iterable)?;
+                let array = self.add_local("", SlotType::Typed(Type::Void));
+                let zero = self.constant(Value::Int(0));
+                self.emit(Op::Constant(zero), iterable.span());
+                self.add_local("", SlotType::Typed(Type::Int));
+                self.emit(Op::Null, name.span);
+                self.add_local(&name.lexeme, SlotType::Element(array));
+
+                let start = self.emit(Op::ForNext(array, 0), iterable.span());
+                self.stmt(body)?;
+                self.emit(Op::Jump(start as u32), name.span);
+                self.patch(start);
+
+                self.end_scope();
+            }
+            Stmt::Return { keyword, value } => {
+                let span = match value {
+                    Some(expr) => {
+                        self.expr(expr)?;
+                        expr.span()
+                    }
+                    None => {
+                        self.emit(Op::Null, keyword.span);
+                        keyword.span
+                    }
+                };
+
+                self.emit(Op::Return, span);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// leaves the converted initial value of the variable on the stack
+    fn initial_value(&mut self, var: &VarDecl) -> CompileResult<()> {
+        match &var.initializer {
+            Some(expr) => {
+                self.expr(expr)?;
+                let ty = self.state.chunk.add_type(var.ty.clone());
+                self.emit(Op::Coerce(ty), expr.span());
+            }
+            None => self.default_value(&var.ty, var.name.span),
+        }
+
+        Ok(())
+    }
+
+    fn default_value(&mut self, ty: &Type, span: TokenSpan) {
+        match Interpreter::default_value(ty) {
+            Value::Null => self.emit(Op::Null, span),
+            Value::Bool(false) => self.emit(Op::False, span),
+            value => {
+                let constant = self.constant(value);
+                self.emit(Op::Constant(constant), span)
+            }
+        };
+    }
+
+    fn expr(&mut self, expr: &Expr) -> CompileResult<()> {
+        match expr {
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                self.expr(left)?;
+                self.expr(right)?;
+
+                let index = self.state.chunk.add_operator(operator.clone());
+                self.emit(Op::Binary(index), operator.span);
+            }
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                self.expr(left)?;
+
+                // the right side is only evaluated if it decides the result
+                let jump = match operator.token_type {
+                    TokenType::Or => self.emit(Op::Or(0), left.span()),
+                    _ => self.emit(Op::And(0), left.span()),
+                };
+                self.expr(right)?;
+                self.patch(jump);
+            }
+            Expr::Unary { operator, right } => {
+                self.expr(right)?;
+
+                let index = self.state.chunk.add_operator(operator.clone());
+                self.emit(Op::Unary(index), operator.span);
+            }
+            Expr::Grouping(expr) => self.expr(expr)?,
+            Expr::Variable(name) => {
+                let op = match self.resolve_local(&name.lexeme) {
+                    Some((slot, _)) => Op::GetLocal(slot),
+                    None => Op::GetGlobal(self.resolve_global(name)?.0),
+                };
+                self.emit(op, name.span);
+            }
+            Expr::Assign {
+                name,
+                operator,
+                value,
+            } => {
+                let (get, set, ty) = match self.resolve_local(&name.lexeme) {
+                    Some((slot, ty)) => (Op::GetLocal(slot), Op::SetLocal(slot), ty),
+                    None => {
+                        let (index, ty) = self.resolve_global(name)?;
+                        (
+                            Op::GetGlobal(index),
+                            Op::SetGlobal(index),
+                            SlotType::Typed(ty),
+                        )
+                    }
+                };
+
+                // like in Java the target is read before the value is evaluated
+                if operator.token_type != TokenType::Equal {
+                    self.emit(get, name.span);
+                }
+                self.expr(value)?;
+                self.compound(operator);
+
+                let coerce = match ty {
+                    SlotType::Typed(ty) => Op::Coerce(self.state.chunk.add_type(ty)),
+                    SlotType::Element(array) => Op::CoerceElement(array),
+                };
+                self.emit(coerce, expr.span());
+                // an undefined global is reported at its name, like by the interpreter
+                self.emit(set, name.span);
+            }
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+            } => {
+                let span = callee.span().to(paren.span);
+
+                match callee.as_ref() {
+                    Expr::Variable(name) => {
+                        let function = match self.function_ids.get(&name.lexeme) {
+                            Some(function) => *function,
+                            None => {
+                                return Err(CompileError {
+                                    code: "E0202",
+                                    message: format!("undefined function '{}'", name.lexeme),
+                                    span: name.span,
+                                })
+                            }
+                        };
+
+                        self.arguments(arguments)?;
+                        self.emit(Op::Call(function, arguments.len() as u32), span);
+                    }
+                    Expr::Get { object, name } => {
+                        self.expr(object)?;
+                        self.emit(Op::CheckObject, object.span());
+                        self.arguments(arguments)?;
+
+                        let name = self.constant(Value::String(name.lexeme.clone()));
+                        self.emit(Op::Invoke(name, arguments.len() as u32), span);
+                    }
+                    _ => {
+                        return Err(CompileError {
+                            code: "E0213",
+                            message: "expression is not callable".to_string(),
+                            span: callee.span(),
+                        })
+                    }
+                }
+            }
+            Expr::Get { object, name } => {
+                self.expr(object)?;
+                self.emit(Op::CheckObject, object.span());
+
+                let constant = self.constant(Value::String(name.lexeme.clone()));
+                self.emit(Op::GetField(constant), name.span);
+            }
+            Expr::Set {
+                object,
+                name,
+                operator,
+                value,
+            } => {
+                self.expr(object)?;
+                self.emit(Op::CheckObject, object.span());
+
+                let constant = self.constant(Value::String(name.lexeme.clone()));
+                if operator.token_type != TokenType::Equal {
+                    self.emit(Op::Dup, object.span());
+                    self.emit(Op::GetField(constant), name.span);
+                }
+                self.expr(value)?;
+                self.compound(operator);
+
+                self.emit(Op::SetField(constant), expr.span());
+            }
+            Expr::Index { object, index, .. } => {
+                self.expr(object)?;
+                self.emit(Op::CheckArray, object.span());
+                self.expr(index)?;
+                self.emit(Op::Index, index.span());
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                operator,
+                value,
+            } => {
+                self.expr(object)?;
+                self.emit(Op::CheckArray, object.span());
+                self.expr(index)?;
+                self.emit(Op::CheckIndex, index.span());
+
+                if operator.token_type != TokenType::Equal {
+                    self.emit(Op::Dup2, index.span());
+                    self.emit(Op::Index, index.span());
+                }
+                self.expr(value)?;
+                self.compound(operator);
+
+                self.emit(Op::SetIndex, expr.span());
+            }
+            Expr::Array(elements, span) => {
+                self.arguments(elements)?;
+                self.emit(Op::Array(elements.len() as u32), *span);
+            }
+            Expr::Builtin {
+                name, arguments, ..
+            } => {
+                self.arguments(arguments)?;
+
+                let name = self.constant(Value::String(name.lexeme.clone()));
+                self.emit(Op::Builtin(name, arguments.len() as u32), expr.span());
+            }
+            Expr::This(keyword) => {
+                if !self.state.has_this {
+                    return Err(CompileError {
+                        code: "E0211",
+                        message: "'this' used outside of a method".to_string(),
+                        span: keyword.span,
+                    });
+                }
+
+                self.emit(Op::GetLocal(0), keyword.span);
+            }
+            Expr::New {
+                keyword,
+                class,
+                paren,
+                arguments,
+            } => {
+                let id = match self.class_ids.get(&class.lexeme) {
+                    Some(id) => *id,
+                    None => {
+                        return Err(CompileError {
+                            code: "E0203",
+                            message: format!("undefined class '{}'", class.lexeme),
+                            span: class.span,
+                        })
+                    }
+                };
+
+                self.arguments(arguments)?;
+                self.emit(
+                    Op::New(id, arguments.len() as u32),
+                    keyword.span.to(paren.span),
+                );
+            }
+            Expr::LiteralTrue(span) => {
+                self.emit(Op::True, *span);
+            }
+            Expr::LiteralFalse(span) => {
+                self.emit(Op::False, *span);
+            }
+            Expr::LiteralNull(span) => {
+                self.emit(Op::Null, *span);
+            }
+            Expr::LiteralInt(i, span) => self.emit_constant(Value::Int(*i), *span),
+            Expr::LiteralDouble(d, span) => self.emit_constant(Value::Double(*d), *span),
+            Expr::LiteralString(s, span) => self.emit_constant(Value::String(s.clone()), *span),
+            Expr::LiteralChar(c, span) => self.emit_constant(Value::Char(*c), *span),
+        }
+
+        Ok(())
+    }
+
+    fn arguments(&mut self, arguments: &[Expr]) -> CompileResult<()> {
+        for argument in arguments {
+            self.expr(argument)?;
+        }
+
+        Ok(())
+    }
+
+    /// applies the operator of a compound assignment to the current and the new value
+    fn compound(&mut self, operator: &Token) {
+        if let Some(token_type) = operator.token_type.compound_operator() {
+            let index = self.state.chunk.add_operator(Token {
+                token_type,
+                lexeme: operator.lexeme.clone(),
+                span: operator.span,
+            });
+            self.emit(Op::Binary(index), operator.span);
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.state.depth += 1;
+    }
+
+    fn end_scope(&mut self) {
+        self.state.depth -= 1;
+
+        let span = self.last_span();
+        while self
+            .state
+            .locals
+            .last()
+            .is_some_and(|local| local.depth > self.state.depth)
+        {
+            self.state.locals.pop();
+            self.emit(Op::Pop, span);
+        }
+    }
+
+    /// declares the value on top of the stack as local and returns its slot
+    fn add_local(&mut self, name: &str, ty: SlotType) -> u32 {
+        self.state.locals.push(Local {
+            name: name.to_string(),
+            ty,
+            depth: self.state.depth,
+        });
+
+        self.state.locals.len() as u32 - 1
+    }
+
+    fn resolve_local(&self, name: &str) -> Option<(u32, SlotType)> {
+        self.state
+            .locals
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, local)| local.name == name)
+            .map(|(slot, local)| (slot as u32, local.ty.clone()))
+    }
+
+    fn resolve_global(&self, name: &Token) -> CompileResult<(u32, Type)> {
+        match self.globals.get(&name.lexeme) {
+            Some(global) => Ok(global.clone()),
+            None => Err(CompileError {
+                code: "E0202",
+                message: format!("undefined variable '{}'", name.lexeme),
+                span: name.span,
+            }),
+        }
+    }
+
+    fn constant(&mut self, value: Value) -> u32 {
+        self.state.chunk.add_constant(value)
+    }
+
+    fn emit_constant(&mut self, value: Value, span: TokenSpan) {
+        let constant = self.constant(value);
+        self.emit(Op::Constant(constant), span);
+    }
+
+    fn emit(&mut self, op: Op, span: TokenSpan) -> usize {
+        self.state.chunk.write(op, span)
+    }
+
+    /// points the jump at the given index to the next instruction
+    fn patch(&mut self, jump: usize) {
+        let target = self.state.chunk.code.len() as u32;
+
+        self.state.chunk.code[jump] = match self.state.chunk.code[jump] {
+            Op::Jump(_) => Op::Jump(target),
+            Op::JumpIfFalse(_) => Op::JumpIfFalse(target),
+            Op::And(_) => Op::And(target),
+            Op::Or(_) => Op::Or(target),
+            Op::ForNext(slot, _) => Op::ForNext(slot, target),
+            op => unreachable!("{:?} is not a jump", op),
+        };
+    }
+
+    fn last_span(&self) -> TokenSpan {
+        self.state
+            .chunk
+            .spans
+            .last()
+            .copied()
+            .unwrap_or(TokenSpan { start: 0, end: 0 })
+    }
+}
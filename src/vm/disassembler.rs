@@ -0,0 +1,160 @@
+use std::fmt::Write;
+
+use super::chunk::{Bytecode, Function, Op};
+use crate::interpreter::heap::Heap;
+use crate::reporter::lines::Lines;
+
+/// lists the instructions of every function, each source line is shown
+/// above the first instruction compiled from it
+pub fn disassemble(bytecode: &Bytecode, source: &str) -> String {
+    let lines = Lines::new(source);
+    let mut output = String::new();
+
+    function(&mut output, bytecode, &bytecode.script, &lines);
+    for f in &bytecode.functions {
+        output.push('\n');
+        function(&mut output, bytecode, f, &lines);
+    }
+
+    output
+}
+
+fn function(output: &mut String, bytecode: &Bytecode, function: &Function, lines: &Lines) {
+    let chunk = &function.chunk;
+    // constants are never objects, so an empty heap is enough to print them
+    let heap = Heap::new();
+
+    writeln!(output, "== {} ==", function.name).unwrap();
+
+    let mut previous = None;
+    for (offset, (op, span)) in chunk.code.iter().zip(&chunk.spans).enumerate() {
+        let line = lines.line(span.start);
+
+        let column = if previous == Some(line) {
+            "   |".to_string()
+        } else {
+            writeln!(output, "{:>9} {}", ";", lines.text(line).trim()).unwrap();
+            format!("{:>4}", line)
+        };
+        previous = Some(line);
+
+        let name = |index: u32| chunk.constants[index as usize].display(&heap).to_string();
+
+        let (mnemonic, operands) = match *op {
+            Op::Constant(index) => ("Constant", format!("{} '{}'", index, name(index))),
+            Op::True => ("True", String::new()),
+            Op::False => ("False", String::new()),
+            Op::Null => ("Null", String::new()),
+            Op::Pop => ("Pop", String::new()),
+            Op::Dup => ("Dup", String::new()),
+            Op::Dup2 => ("Dup2", String::new()),
+            Op::GetLocal(slot) => ("GetLocal", slot.to_string()),
+            Op::SetLocal(slot) => ("SetLocal", slot.to_string()),
+            Op::GetGlobal(index) => (
+                "GetGlobal",
+                format!("{} {}", index, bytecode.globals[index as usize]),
+            ),
+            Op::SetGlobal(index) => (
+                "SetGlobal",
+                format!("{} {}", index, bytecode.globals[index as usize]),
+            ),
+            Op::DefineGlobal(index) => (
+                "DefineGlobal",
+                format!("{} {}", index, bytecode.globals[index as usize]),
+            ),
+            Op::Coerce(index) => ("Coerce", chunk.types[index as usize].to_string()),
+            Op::CoerceElement(slot) => ("CoerceElement", slot.to_string()),
+            Op::Unary(index) => ("Unary", chunk.operators[index as usize].lexeme.clone()),
+            Op::Binary(index) => ("Binary", chunk.operators[index as usize].lexeme.clone()),
+            Op::Jump(target) => ("Jump", format!("-> {:04}", target)),
+            Op::JumpIfFalse(target) => ("JumpIfFalse", format!("-> {:04}", target)),
+            Op::And(target) => ("And", format!("-> {:04}", target)),
+            Op::Or(target) => ("Or", format!("-> {:04}", target)),
+            Op::Call(id, argc) => (
+                "Call",
+                format!("{} ({} args)", bytecode.functions[id as usize].name, argc),
+            ),
+            Op::Invoke(index, argc) => ("Invoke", format!("{} ({} args)", name(index), argc)),
+            Op::New(id, argc) => (
+                "New",
+                format!(
+                    "{} ({} args)",
+                    bytecode.classes[id as usize].decl.name.lexeme, argc
+                ),
+            ),
+            Op::Builtin(index, argc) => ("Builtin", format!("{}! ({} args)", name(index), argc)),
+            Op::Return => ("Return", String::new()),
+            Op::Construct(id) => (
+                "Construct",
+                bytecode.classes[id as usize].decl.name.lexeme.clone(),
+            ),
+            Op::CheckObject => ("CheckObject", String::new()),
+            Op::GetField(index) => ("GetField", name(index)),
+            Op::SetField(index) => ("SetField", name(index)),
+            Op::CheckArray => ("CheckArray", String::new()),
+            Op::CheckIndex => ("CheckIndex", String::new()),
+            Op::Index => ("Index", String::new()),
+            Op::SetIndex => ("SetIndex", String::new()),
+            Op::Array(count) => ("Array", count.to_string()),
+            Op::ForNext(slot, target) => ("ForNext", format!("{} -> {:04}", slot, target)),
+        };
+
+        let instruction = format!("{:04} {} {:<14} {}", offset, column, mnemonic, operands);
+        writeln!(output, "{}", instruction.trim_end()).unwrap();
+    }
+}
+
+#[test]
+fn test_disassemble() {
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    let source = "int limit = 3;
+fn main() {
+    for (int i = 0; i < limit; i += 1) {
+        out!(i);
+    }
+}";
+
+    let tokens = Lexer::from_string(source.to_string()).lex_tokens().0;
+    let program = Parser::new(tokens).parse().unwrap();
+    let bytecode = super::compiler::compile(&program).unwrap();
+
+    let expected = "\
+== <script> ==
+        ; int limit = 3;
+0000    1 Constant       0 '3'
+0001    | Coerce         int
+0002    | DefineGlobal   0 limit
+0003    | Pop
+0004    | Null
+0005    | Return
+
+== main ==
+        ; for (int i = 0; i < limit; i += 1) {
+0000    3 Constant       0 '0'
+0001    | Coerce         int
+0002    | GetLocal       0
+0003    | GetGlobal      0 limit
+0004    | Binary         <
+0005    | JumpIfFalse    -> 0016
+        ; out!(i);
+0006    4 GetLocal       0
+0007    | Builtin        out! (1 args)
+0008    | Pop
+        ; for (int i = 0; i < limit; i += 1) {
+0009    3 GetLocal       0
+0010    | Constant       2 '1'
+0011    | Binary         +=
+0012    | Coerce         int
+0013    | SetLocal       0
+0014    | Pop
+0015    | Jump           -> 0002
+0016    | Pop
+        ; fn main() {
+0017    2 Null
+0018    | Return
+";
+
+    assert_eq!(disassemble(&bytecode, source), expected);
+}